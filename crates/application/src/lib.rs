@@ -3,8 +3,8 @@ use std::ops::{Deref, DerefMut};
 use utilities::{tracing, EcsContext, EcsPlugin, Event};
 use windowing::{
     events::Create,
-    window::{Window, WindowId},
-    CallbackTranslator, EventLoop,
+    window::{Window, WindowDescriptor, WindowId},
+    CallbackTranslator, EventLoop, QuitPolicy,
 };
 
 pub struct ApplicationContext<'a, T>(&'a mut EcsContext<T>);
@@ -13,14 +13,21 @@ impl<'a, T> ApplicationContext<'a, T> {
     pub(crate) fn new(ctx: &'a mut EcsContext<T>) -> Self {
         Self(ctx)
     }
-    pub fn create_window(&mut self, window: impl Window) -> WindowId {
+    /// Opens a window set up as `descriptor` describes, `window` receives its callbacks.
+    pub fn create_window(&mut self, window: impl Window, descriptor: WindowDescriptor) -> WindowId {
         let window_id = WindowId::new();
         self.0.post_event(Create {
             window: Box::new(window),
             window_id,
+            descriptor,
         });
         window_id
     }
+    /// Decides when closing windows quits the application, by default once they are all
+    /// closed.
+    pub fn set_quit_policy(&mut self, policy: QuitPolicy) {
+        self.0.world.insert_resource(policy);
+    }
     pub fn post_event<E>(&mut self, event: E)
     where
         E: Event,
@@ -64,6 +64,15 @@ impl From<(u8, u8, u8, u8)> for Colour {
     }
 }
 
+impl From<Colour> for skia::Color {
+    fn from(colour: Colour) -> skia::Color {
+        match colour {
+            Colour::Rgb(Rgb { r, g, b }) => skia::Color::from_rgb(r, g, b),
+            Colour::Rgba(Rgba { r, g, b, a }) => skia::Color::from_argb(a, r, g, b),
+        }
+    }
+}
+
 impl Colour {
     // White Colours
     make_colour! { WHITE            [255, 255, 255] }
@@ -0,0 +1,80 @@
+use skia::IRect;
+
+use crate::painter::Rect;
+
+/// The part of a surface that changed since it was last presented.
+///
+/// Multiple areas are merged into the rectangle that bounds all of them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Damage {
+    Empty,
+    Area(Rect),
+    Full,
+}
+
+impl Damage {
+    pub fn add(&mut self, damage: Damage) {
+        *self = match (*self, damage) {
+            (Damage::Full, _) | (_, Damage::Full) => Damage::Full,
+            (Damage::Empty, other) | (other, Damage::Empty) => other,
+            (Damage::Area(a), Damage::Area(b)) => {
+                let left = a.x.min(b.x);
+                let top = a.y.min(b.y);
+                let right = (a.x + a.width).max(b.x + b.width);
+                let bottom = (a.y + a.height).max(b.y + b.height);
+                Damage::Area(Rect::new(left, top, right - left, bottom - top))
+            }
+        };
+    }
+
+    pub fn add_area(&mut self, area: impl Into<Rect>) {
+        self.add(Damage::Area(area.into()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Damage::Empty)
+    }
+
+    /// Converts an area in logical units to physical pixels.
+    pub(crate) fn scaled(self, scale_factor: f32) -> Damage {
+        match self {
+            Damage::Area(area) if scale_factor != 1.0 => Damage::Area(Rect::new(
+                area.x * scale_factor,
+                area.y * scale_factor,
+                area.width * scale_factor,
+                area.height * scale_factor,
+            )),
+            other => other,
+        }
+    }
+
+    /// The damaged pixels of a `width` by `height` surface, `None` when the whole
+    /// surface has to be presented. Areas are grown to whole pixels so anti-aliased
+    /// edges are never left behind.
+    pub(crate) fn to_pixels(self, width: i32, height: i32) -> Option<IRect> {
+        let area = match self {
+            Damage::Area(area) => area,
+            Damage::Empty | Damage::Full => return None,
+        };
+        let left = (area.x.floor() as i32).clamp(0, width);
+        let top = (area.y.floor() as i32).clamp(0, height);
+        let right = ((area.x + area.width).ceil() as i32).clamp(0, width);
+        let bottom = ((area.y + area.height).ceil() as i32).clamp(0, height);
+        if (left, top, right, bottom) == (0, 0, width, height) {
+            return None;
+        }
+        Some(IRect::new(left, top, right.max(left), bottom.max(top)))
+    }
+}
+
+impl Default for Damage {
+    fn default() -> Self {
+        Damage::Empty
+    }
+}
+
+impl From<Rect> for Damage {
+    fn from(area: Rect) -> Self {
+        Damage::Area(area)
+    }
+}
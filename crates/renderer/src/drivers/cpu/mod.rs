@@ -3,36 +3,118 @@ use crate::WindowDrawTarget;
 
 use super::Driver;
 use anyhow::Error;
-use skia::{ISize, Surface};
+use skia::{IRect, ISize, ImageInfo};
 
 use platform::{BackBuffer, ConcretePlatformImpl, PlatformApi};
 use utilities::tracing;
 
-pub struct Cpu(pub(crate) skia::Surface);
+pub struct Cpu(pub(crate) RasterStore, ConcretePlatformImpl);
+
+impl Cpu {
+    pub fn new(size: impl Into<ISize>) -> anyhow::Result<Self> {
+        Ok(Self(
+            RasterStore::new(size)?,
+            ConcretePlatformImpl::default(),
+        ))
+    }
+}
 
 impl Driver for Cpu {
-    fn create_surface(
-        &mut self,
-        _: &impl WindowDrawTarget,
-        size: impl Into<ISize>,
-    ) -> anyhow::Result<skia::Surface> {
-        Surface::new_raster_n32_premul(size.into())
-            .map_or_else(|| Err(Error::msg("Unable to create Surface")), |e| Ok(e))
+    fn surface(&mut self) -> &mut skia::Surface {
+        &mut self.0.surface
+    }
+    fn size(&self) -> ISize {
+        self.0.size()
+    }
+    fn resize_surface(&mut self, _: &impl WindowDrawTarget, size: ISize) -> anyhow::Result<()> {
+        self.0.resize(size)
     }
     fn present_surface(
         &mut self,
         window: &impl WindowDrawTarget,
-        surface: &mut skia::Surface,
+        area: Option<IRect>,
     ) -> anyhow::Result<()> {
-        tracing::debug_span!("SkiaDriver::new_cpu");
-        let back_buffer = <ConcretePlatformImpl as PlatformApi>::BackBuffer::new(surface)?;
-        <ConcretePlatformImpl as PlatformApi>::present_backbuffer(window, back_buffer)?;
+        tracing::debug_span!("Cpu::present_surface");
+        // The back buffer borrows the pixels of the surface, nothing is copied here.
+        let back_buffer =
+            <ConcretePlatformImpl as PlatformApi>::BackBuffer::new(&mut self.0.surface)?;
+        self.1.present_backbuffer(window, back_buffer, area)?;
         Ok(())
     }
 }
 
-impl Default for Cpu {
-    fn default() -> Self {
-        Self(skia::Surface::new_null((1, 1)).unwrap())
+/// The pixels every CPU driver draws into, they are not tied to any window.
+///
+/// The pixels are allocated for the size class of the surface, resizing within it only
+/// wraps a new skia surface around the same pixels with the same row stride, so nothing
+/// is allocated or copied and what was drawn stays in place. Pixels are stored as `u32`
+/// so they are aligned for skia, which reads N32 pixels as whole words.
+pub(crate) struct RasterStore {
+    // Declared first so the surface is dropped before the pixels it draws into.
+    pub(crate) surface: skia::Surface,
+    pixels: Vec<u32>,
+    capacity: ISize,
+}
+
+impl RasterStore {
+    pub(crate) fn new(size: impl Into<ISize>) -> anyhow::Result<Self> {
+        let size = size.into();
+        let capacity = super::size_class(size);
+        let mut pixels = vec![0; pixel_count(capacity)];
+        let surface = wrap_pixels(&mut pixels, capacity, size)?;
+        Ok(Self {
+            surface,
+            pixels,
+            capacity,
+        })
+    }
+
+    pub(crate) fn size(&self) -> ISize {
+        ISize::new(self.surface.width(), self.surface.height())
     }
+
+    pub(crate) fn resize(&mut self, size: ISize) -> anyhow::Result<()> {
+        tracing::debug_span!("RasterStore::resize");
+        if super::fits_size_class(self.capacity, size) {
+            self.surface = wrap_pixels(&mut self.pixels, self.capacity, size)?;
+            return Ok(());
+        }
+
+        let capacity = super::size_class(size);
+        let mut pixels = vec![0; pixel_count(capacity)];
+        let (old_stride, new_stride) = (self.capacity.width as usize, capacity.width as usize);
+        let old_size = self.size();
+        let row_len = old_size.width.min(size.width) as usize;
+        for row in 0..old_size.height.min(size.height) as usize {
+            pixels[row * new_stride..row * new_stride + row_len]
+                .copy_from_slice(&self.pixels[row * old_stride..row * old_stride + row_len]);
+        }
+        // The old surface has to go before the pixels it draws into.
+        self.surface = wrap_pixels(&mut pixels, capacity, size)?;
+        self.pixels = pixels;
+        self.capacity = capacity;
+        Ok(())
+    }
+}
+
+fn pixel_count(capacity: ISize) -> usize {
+    capacity.width as usize * capacity.height as usize
+}
+
+fn row_bytes(capacity: ISize) -> usize {
+    capacity.width as usize * 4
+}
+
+/// A `size` surface drawing into `pixels`, which hold a `capacity` image.
+fn wrap_pixels(pixels: &mut [u32], capacity: ISize, size: ISize) -> anyhow::Result<skia::Surface> {
+    let image_info = ImageInfo::new_n32_premul(size, None);
+    // SAFETY: the bytes cover exactly the same memory as `pixels`, `u32` has no padding and
+    // every byte pattern is a valid `u8`.
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, pixels.len() * 4) };
+    let surface = skia::Surface::new_raster_direct(&image_info, bytes, row_bytes(capacity), None)
+        .map_or_else(|| Err(Error::msg("Unable to create Surface")), |e| Ok(e))?;
+    // SAFETY: the pixels are owned by the RasterStore next to the surface, which drops the
+    // surface before them and never reallocates them while the surface is alive.
+    Ok(unsafe { surface.release() })
 }
@@ -3,7 +3,25 @@ pub mod win32;
 #[cfg(target_os = "windows")]
 pub use win32::Win32PlatformImpl as ConcretePlatformImpl;
 
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub mod unix;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub use unix::UnixPlatformImpl as ConcretePlatformImpl;
+
 use crate::WindowDrawTarget;
+use skia::IRect;
 
 pub trait BackBuffer {
     fn new(surface: &mut skia::Surface) -> anyhow::Result<Self>
@@ -11,10 +29,17 @@ pub trait BackBuffer {
         Self: Sized;
 }
 
-pub trait PlatformApi {
+/// Platform specific presentation of a [`BackBuffer`].
+///
+/// An instance lives alongside each CPU surface so implementations can keep
+/// whatever they need between presents (loaded libraries, shared memory, ...).
+pub trait PlatformApi: Default {
     type BackBuffer: BackBuffer;
+    /// Copies `area` of the back buffer to the window, the whole back buffer when `None`.
     fn present_backbuffer(
+        &mut self,
         window: &impl WindowDrawTarget,
         back_buffer: Self::BackBuffer,
+        area: Option<IRect>,
     ) -> anyhow::Result<()>;
 }
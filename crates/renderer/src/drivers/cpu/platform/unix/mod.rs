@@ -0,0 +1,82 @@
+pub mod wayland;
+pub mod x11;
+
+use anyhow::Error;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+use skia::IRect;
+
+use super::{BackBuffer, PlatformApi};
+use crate::WindowDrawTarget;
+use wayland::WaylandPlatformImpl;
+use x11::X11PlatformImpl;
+
+/// Picks the X11 or Wayland backend from the handle of the window being presented to.
+#[derive(Default)]
+pub struct UnixPlatformImpl {
+    x11: X11PlatformImpl,
+    wayland: WaylandPlatformImpl,
+}
+
+impl PlatformApi for UnixPlatformImpl {
+    type BackBuffer = RasterBackBuffer;
+    fn present_backbuffer(
+        &mut self,
+        window: &impl WindowDrawTarget,
+        back_buffer: Self::BackBuffer,
+        area: Option<IRect>,
+    ) -> anyhow::Result<()> {
+        match window.raw_window_handle() {
+            RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_) => {
+                self.x11.present_backbuffer(window, back_buffer, area)
+            }
+            RawWindowHandle::Wayland(_) => {
+                self.wayland.present_backbuffer(window, back_buffer, area)
+            }
+            _ => Err(Error::msg("Called for the wrong platform")),
+        }
+    }
+}
+
+/// A borrowed view of the skia surface pixels, shared by the unix backends.
+pub struct RasterBackBuffer {
+    pub width: i32,
+    pub height: i32,
+    pub stride: usize,
+    pub data: *const u8,
+}
+
+impl RasterBackBuffer {
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.data, self.stride * self.height as usize) }
+    }
+
+    /// `area` clamped to the back buffer, `None` becomes the whole buffer.
+    pub fn clamp_area(&self, area: Option<IRect>) -> IRect {
+        let full = IRect::from_wh(self.width, self.height);
+        match area {
+            Some(area) => IRect::intersect(&area, &full).unwrap_or_default(),
+            None => full,
+        }
+    }
+}
+
+impl BackBuffer for RasterBackBuffer {
+    fn new(surface: &mut skia::Surface) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let width = surface.width();
+        let height = surface.height();
+        let tlp = match surface.canvas().access_top_layer_pixels() {
+            Some(tlp) => tlp,
+            None => return Err(Error::msg("Surface pixels are not accessible")),
+        };
+
+        Ok(RasterBackBuffer {
+            width,
+            height,
+            stride: tlp.row_bytes,
+            data: tlp.pixels.as_ptr(),
+        })
+    }
+}
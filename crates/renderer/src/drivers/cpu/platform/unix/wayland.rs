@@ -0,0 +1,340 @@
+use std::{
+    fs::File,
+    io,
+    os::{
+        raw::c_void,
+        unix::io::{AsRawFd, FromRawFd},
+    },
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Error;
+use memmap2::MmapMut;
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+};
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle, WaylandHandle};
+use skia::IRect;
+use wayland_client::{
+    protocol::{
+        wl_buffer::{self, WlBuffer},
+        wl_shm::{self, WlShm},
+        wl_shm_pool::WlShmPool,
+        wl_surface::WlSurface,
+    },
+    sys::client::{wl_display, wl_proxy},
+    Display, EventQueue, GlobalManager, Main, Proxy,
+};
+
+use super::{super::PlatformApi, RasterBackBuffer};
+use crate::WindowDrawTarget;
+use utilities::tracing;
+
+/// How many buffers a surface may have in flight before presenting waits for the
+/// compositor to release one.
+const MAX_BUFFERS_IN_FLIGHT: usize = 3;
+
+/// How long presenting waits for a buffer to be released before skipping the frame.
+/// Compositors can hold on to the buffers of hidden surfaces indefinitely.
+const BUFFER_RELEASE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Presents the CPU back buffer to a Wayland surface through `wl_shm`.
+///
+/// Skia's N32 colour type is BGRA premultiplied on little-endian machines,
+/// which is exactly `wl_shm::Format::Argb8888`, so frames are a straight copy.
+#[derive(Default)]
+pub struct WaylandPlatformImpl {
+    connection: Option<WaylandConnection>,
+}
+
+impl PlatformApi for WaylandPlatformImpl {
+    type BackBuffer = RasterBackBuffer;
+    fn present_backbuffer(
+        &mut self,
+        window: &impl WindowDrawTarget,
+        back_buffer: Self::BackBuffer,
+        area: Option<IRect>,
+    ) -> anyhow::Result<()> {
+        let handle = match window.raw_window_handle() {
+            RawWindowHandle::Wayland(handle) => handle,
+            _ => return Err(Error::msg("Called for the wrong platform")),
+        };
+        if handle.display.is_null() || handle.surface.is_null() {
+            return Err(Error::msg("Wayland window handle is incomplete"));
+        }
+
+        let area = back_buffer.clamp_area(area);
+        if area.is_empty() {
+            return Ok(());
+        }
+
+        let is_stale = match self.connection {
+            Some(ref connection) => connection.surface_ptr != handle.surface,
+            None => true,
+        };
+        if is_stale {
+            self.connection = Some(WaylandConnection::new(handle)?);
+        }
+        self.connection
+            .as_mut()
+            .unwrap()
+            .present(&back_buffer, area)
+    }
+}
+
+struct WaylandConnection {
+    // Declared first so the buffers are destroyed before the queue they belong to.
+    buffers: Vec<ShmBuffer>,
+    // The damage of frames that were skipped because no buffer was free.
+    skipped_damage: Option<IRect>,
+    surface_ptr: *mut c_void,
+    surface: WlSurface,
+    shm: Main<WlShm>,
+    event_queue: EventQueue,
+    display: Display,
+}
+
+impl WaylandConnection {
+    fn new(handle: WaylandHandle) -> anyhow::Result<Self> {
+        tracing::debug_span!("WaylandConnection::new");
+        // SAFETY: both pointers come from the windowing library, which keeps them alive
+        // for as long as the window exists.
+        let display = unsafe { Display::from_external_display(handle.display as *mut wl_display) };
+        let surface: WlSurface =
+            unsafe { Proxy::<WlSurface>::from_c_ptr(handle.surface as *mut wl_proxy) }.into();
+
+        // Our objects get a private queue so their events never reach the windowing library.
+        let mut event_queue = display.create_event_queue();
+        let attached_display = display.attach(event_queue.token());
+        let globals = GlobalManager::new(&attached_display);
+        event_queue.sync_roundtrip(&mut (), |_, _, _| {})?;
+        let shm = globals.instantiate_exact::<WlShm>(1)?;
+
+        Ok(Self {
+            buffers: Vec::new(),
+            skipped_damage: None,
+            surface_ptr: handle.surface,
+            surface,
+            shm,
+            event_queue,
+            display,
+        })
+    }
+
+    /// Buffers are recycled, so the whole back buffer is always copied into one, only
+    /// `area` is reported to the compositor as damaged. When every buffer is still held by
+    /// the compositor the frame is skipped and its damage is reported with the next one.
+    fn present(&mut self, back_buffer: &RasterBackBuffer, area: IRect) -> anyhow::Result<()> {
+        let (width, height, stride) = (back_buffer.width, back_buffer.height, back_buffer.stride);
+
+        // Pick up any release events that arrived since the last frame.
+        self.event_queue.dispatch_pending(&mut (), |_, _, _| {})?;
+        self.drop_stale_buffers(width, height, stride);
+
+        let mut index = self.find_free_buffer(width, height, stride);
+        if index.is_none() && self.buffers.len() >= MAX_BUFFERS_IN_FLIGHT {
+            index = self.wait_for_free_buffer(width, height, stride)?;
+        }
+        let index = match index {
+            Some(index) => index,
+            None if self.buffers.len() < MAX_BUFFERS_IN_FLIGHT => {
+                self.buffers
+                    .push(ShmBuffer::new(&self.shm, width, height, stride)?);
+                self.buffers.len() - 1
+            }
+            None => {
+                tracing::debug!("No wl_buffer was released in time, skipping the frame");
+                self.skipped_damage = Some(join_area(self.skipped_damage, area));
+                return Ok(());
+            }
+        };
+        let area = join_area(self.skipped_damage.take(), area);
+
+        let buffer = &mut self.buffers[index];
+        buffer.mmap.copy_from_slice(back_buffer.as_slice());
+        buffer.released.store(false, Ordering::Release);
+
+        self.surface.attach(Some(&*buffer.buffer), 0, 0);
+        // damage_buffer is only available from wl_surface version 4
+        if self.surface.as_ref().version() >= 4 {
+            self.surface
+                .damage_buffer(area.left, area.top, area.width(), area.height());
+        } else {
+            self.surface.damage(0, 0, i32::MAX, i32::MAX);
+        }
+        self.surface.commit();
+
+        match self.display.flush() {
+            Ok(_) => Ok(()),
+            // The requests stay queued and go out with the next flush.
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Reads events until a buffer is released, for at most [`BUFFER_RELEASE_TIMEOUT`].
+    /// Returns `None` when none was released in time or buffers were dropped instead.
+    fn wait_for_free_buffer(
+        &mut self,
+        width: i32,
+        height: i32,
+        stride: usize,
+    ) -> anyhow::Result<Option<usize>> {
+        tracing::debug_span!("WaylandConnection::wait_for_free_buffer");
+        let deadline = Instant::now() + BUFFER_RELEASE_TIMEOUT;
+        loop {
+            self.event_queue.dispatch_pending(&mut (), |_, _, _| {})?;
+            self.drop_stale_buffers(width, height, stride);
+            let index = self.find_free_buffer(width, height, stride);
+            if index.is_some() || self.buffers.len() < MAX_BUFFERS_IN_FLIGHT {
+                return Ok(index);
+            }
+
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return Ok(None);
+            }
+            // The compositor can only release buffers for commits it has received.
+            match self.display.flush() {
+                Ok(_) => (),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => return Err(err.into()),
+            }
+            // `None` means events are already queued, they are dispatched above.
+            let guard = match self.event_queue.prepare_read() {
+                Some(guard) => guard,
+                None => continue,
+            };
+            let mut fds = [PollFd::new(
+                self.display.get_connection_fd(),
+                PollFlags::POLLIN,
+            )];
+            match poll(&mut fds, timeout.as_millis() as i32) {
+                Ok(0) => return Ok(None),
+                Ok(_) => match guard.read_events() {
+                    Ok(_) => (),
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                    Err(err) => return Err(err.into()),
+                },
+                Err(Errno::EINTR) => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Buffers from before a resize are dropped once the compositor is done with them.
+    fn drop_stale_buffers(&mut self, width: i32, height: i32, stride: usize) {
+        self.buffers
+            .retain(|buffer| !buffer.is_released() || buffer.fits(width, height, stride));
+    }
+
+    fn find_free_buffer(&self, width: i32, height: i32, stride: usize) -> Option<usize> {
+        self.buffers
+            .iter()
+            .position(|buffer| buffer.is_released() && buffer.fits(width, height, stride))
+    }
+}
+
+fn join_area(skipped: Option<IRect>, area: IRect) -> IRect {
+    match skipped {
+        Some(skipped) => IRect::join(&skipped, &area),
+        None => area,
+    }
+}
+
+struct ShmBuffer {
+    buffer: Main<WlBuffer>,
+    pool: Main<WlShmPool>,
+    mmap: MmapMut,
+    _file: File,
+    released: Arc<AtomicBool>,
+    width: i32,
+    height: i32,
+    stride: usize,
+}
+
+impl ShmBuffer {
+    fn new(shm: &Main<WlShm>, width: i32, height: i32, stride: usize) -> anyhow::Result<Self> {
+        let size = stride * height as usize;
+        let file = create_shm_file(size)?;
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        let pool = shm.create_pool(file.as_raw_fd(), size as i32);
+        let buffer = pool.create_buffer(0, width, height, stride as i32, wl_shm::Format::Argb8888);
+        let released = Arc::new(AtomicBool::new(true));
+        {
+            let released = released.clone();
+            buffer.quick_assign(move |_, event, _| {
+                if let wl_buffer::Event::Release = event {
+                    released.store(true, Ordering::Release);
+                }
+            });
+        }
+
+        Ok(Self {
+            buffer,
+            pool,
+            mmap,
+            _file: file,
+            released,
+            width,
+            height,
+            stride,
+        })
+    }
+
+    fn is_released(&self) -> bool {
+        self.released.load(Ordering::Acquire)
+    }
+
+    fn fits(&self, width: i32, height: i32, stride: usize) -> bool {
+        self.width == width && self.height == height && self.stride == stride
+    }
+}
+
+impl Drop for ShmBuffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+        self.pool.destroy();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn create_shm_file(size: usize) -> anyhow::Result<File> {
+    use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+    use std::ffi::CStr;
+
+    let name = CStr::from_bytes_with_nul(b"renderer-wl-shm\0").unwrap();
+    let fd = memfd_create(name, MemFdCreateFlag::MFD_CLOEXEC)?;
+    let file = unsafe { File::from_raw_fd(fd) };
+    file.set_len(size as u64)?;
+    Ok(file)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn create_shm_file(size: usize) -> anyhow::Result<File> {
+    use nix::{
+        fcntl::OFlag,
+        sys::{
+            mman::{shm_open, shm_unlink},
+            stat::Mode,
+        },
+    };
+
+    let name = format!("/renderer-wl-shm-{}", std::process::id());
+    let fd = shm_open(
+        name.as_str(),
+        OFlag::O_CREAT | OFlag::O_EXCL | OFlag::O_RDWR | OFlag::O_CLOEXEC,
+        Mode::S_IRUSR | Mode::S_IWUSR,
+    )?;
+    // Only the file descriptor is shared with the compositor, the name can go straight away.
+    shm_unlink(name.as_str())?;
+    let file = unsafe { File::from_raw_fd(fd) };
+    file.set_len(size as u64)?;
+    Ok(file)
+}
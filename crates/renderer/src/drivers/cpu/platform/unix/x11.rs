@@ -0,0 +1,220 @@
+use std::{
+    os::raw::{c_char, c_void},
+    ptr,
+};
+
+use anyhow::Error;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle, XcbHandle, XlibHandle};
+use skia::IRect;
+use x11_dl::xlib::{self, Xlib};
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::xproto::{ConnectionExt, CreateGCAux, ImageFormat},
+    xcb_ffi::XCBConnection,
+};
+
+use super::{super::PlatformApi, RasterBackBuffer};
+use crate::WindowDrawTarget;
+
+/// Presents the CPU back buffer to an X11 window with a plain `PutImage`.
+///
+/// Skia's N32 colour type is BGRA on little-endian Linux, which is the byte
+/// layout of a 24/32 bit TrueColor `ZPixmap`, so the pixels go over the wire
+/// without any conversion.
+#[derive(Default)]
+pub struct X11PlatformImpl {
+    // Loaded lazily so that a process which only ever sees Xcb handles never
+    // has to dlopen libX11.
+    xlib: Option<Xlib>,
+    xcb: Option<XcbState>,
+}
+
+/// The Xcb connection of the last window presented to, along with what had to be asked
+/// of the server about it so it is not asked again every frame.
+struct XcbState {
+    connection_ptr: *mut c_void,
+    conn: XCBConnection,
+    // Querying it the first time costs a BIG-REQUESTS round trip.
+    maximum_request_bytes: usize,
+    window: u32,
+    depth: u8,
+}
+
+/// The size of a `PutImage` request without its pixels, including the extended length
+/// field BIG-REQUESTS adds.
+const PUT_IMAGE_HEADER_BYTES: usize = 28;
+
+impl PlatformApi for X11PlatformImpl {
+    type BackBuffer = RasterBackBuffer;
+    fn present_backbuffer(
+        &mut self,
+        window: &impl WindowDrawTarget,
+        back_buffer: Self::BackBuffer,
+        area: Option<IRect>,
+    ) -> anyhow::Result<()> {
+        let area = back_buffer.clamp_area(area);
+        if area.is_empty() {
+            return Ok(());
+        }
+        match window.raw_window_handle() {
+            RawWindowHandle::Xlib(handle) => self.present_xlib(handle, &back_buffer, area),
+            RawWindowHandle::Xcb(handle) => self.present_xcb(handle, &back_buffer, area),
+            _ => Err(Error::msg("Called for the wrong platform")),
+        }
+    }
+}
+
+impl X11PlatformImpl {
+    fn present_xlib(
+        &mut self,
+        handle: XlibHandle,
+        back_buffer: &RasterBackBuffer,
+        area: IRect,
+    ) -> anyhow::Result<()> {
+        if handle.display.is_null() || handle.window == 0 {
+            return Err(Error::msg("Xlib window handle is incomplete"));
+        }
+        if self.xlib.is_none() {
+            self.xlib = Some(Xlib::open()?);
+        }
+        let xlib = self.xlib.as_ref().unwrap();
+        let display = handle.display as *mut xlib::Display;
+
+        let mut attributes = unsafe { std::mem::zeroed::<xlib::XWindowAttributes>() };
+        if unsafe { (xlib.XGetWindowAttributes)(display, handle.window, &mut attributes) } == 0 {
+            return Err(Error::msg("Unable to query the X11 window attributes"));
+        }
+        if attributes.depth != 24 && attributes.depth != 32 {
+            return Err(Error::msg(format!(
+                "Unsupported X11 visual depth: {}",
+                attributes.depth
+            )));
+        }
+
+        unsafe {
+            let image = (xlib.XCreateImage)(
+                display,
+                attributes.visual,
+                attributes.depth as u32,
+                xlib::ZPixmap,
+                0,
+                back_buffer.data as *mut c_char,
+                back_buffer.width as u32,
+                back_buffer.height as u32,
+                32,
+                back_buffer.stride as i32,
+            );
+            if image.is_null() {
+                return Err(Error::msg("Unable to create XImage"));
+            }
+            let gc = (xlib.XCreateGC)(display, handle.window, 0, ptr::null_mut());
+            (xlib.XPutImage)(
+                display,
+                handle.window,
+                gc,
+                image,
+                area.left,
+                area.top,
+                area.left,
+                area.top,
+                area.width() as u32,
+                area.height() as u32,
+            );
+            (xlib.XFreeGC)(display, gc);
+
+            // The pixels belong to the skia surface, stop XDestroyImage from freeing them.
+            (*image).data = ptr::null_mut();
+            (xlib.XDestroyImage)(image);
+            (xlib.XFlush)(display);
+        }
+        Ok(())
+    }
+
+    fn present_xcb(
+        &mut self,
+        handle: XcbHandle,
+        back_buffer: &RasterBackBuffer,
+        area: IRect,
+    ) -> anyhow::Result<()> {
+        if handle.connection.is_null() || handle.window == 0 {
+            return Err(Error::msg("Xcb window handle is incomplete"));
+        }
+        let is_stale = match self.xcb {
+            Some(ref xcb) => xcb.connection_ptr != handle.connection,
+            None => true,
+        };
+        if is_stale {
+            // SAFETY: the connection is owned by the windowing library and outlives the
+            // window, the wrapper does not disconnect it when dropped.
+            let conn = unsafe { XCBConnection::from_raw_xcb_connection(handle.connection, false)? };
+            let maximum_request_bytes = conn.maximum_request_bytes();
+            self.xcb = Some(XcbState {
+                connection_ptr: handle.connection,
+                conn,
+                maximum_request_bytes,
+                window: 0,
+                depth: 0,
+            });
+        }
+        let xcb = self.xcb.as_mut().unwrap();
+        if xcb.window != handle.window {
+            xcb.depth = xcb.conn.get_geometry(handle.window)?.reply()?.depth;
+            xcb.window = handle.window;
+        }
+        let (conn, depth) = (&xcb.conn, xcb.depth);
+        if depth != 24 && depth != 32 {
+            return Err(Error::msg(format!(
+                "Unsupported X11 visual depth: {}",
+                depth
+            )));
+        }
+
+        let gc = conn.generate_id()?;
+        conn.create_gc(gc, handle.window, &CreateGCAux::new())?;
+
+        // A single PutImage request is limited in size, large areas are sent in bands of
+        // rows. Rows longer than a request allows are split into runs of columns.
+        let max_pixels = (xcb.maximum_request_bytes - PUT_IMAGE_HEADER_BYTES) / 4;
+        let columns_per_request = (area.width() as usize).min(max_pixels);
+        let rows_per_request = (max_pixels / columns_per_request).max(1);
+        let stride = back_buffer.stride;
+        let data = back_buffer.as_slice();
+        let mut band = Vec::new();
+        let mut row = area.top as usize;
+        while row < area.bottom as usize {
+            let rows = rows_per_request.min(area.bottom as usize - row);
+            let mut column = area.left as usize;
+            while column < area.right as usize {
+                let columns = columns_per_request.min(area.right as usize - column);
+                let (left, right) = (column * 4, (column + columns) * 4);
+                let pixels = if left == 0 && right == stride {
+                    &data[row * stride..(row + rows) * stride]
+                } else {
+                    // Partial rows are not contiguous in the back buffer, pack them first.
+                    band.clear();
+                    for row in row..row + rows {
+                        band.extend_from_slice(&data[row * stride + left..row * stride + right]);
+                    }
+                    &band[..]
+                };
+                conn.put_image(
+                    ImageFormat::Z_PIXMAP,
+                    handle.window,
+                    gc,
+                    columns as u16,
+                    rows as u16,
+                    column as i16,
+                    row as i16,
+                    0,
+                    depth,
+                    pixels,
+                )?;
+                column += columns;
+            }
+            row += rows;
+        }
+        conn.free_gc(gc)?;
+        conn.flush()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use std::mem;
 use anyhow::Error;
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle, Win32Handle};
+use skia::IRect;
 use windows::Win32::Foundation::{HINSTANCE, HWND};
 use windows::Win32::Graphics::Gdi::{
     GetDC, ReleaseDC, StretchDIBits, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, SRCCOPY,
@@ -9,20 +10,42 @@ use windows::Win32::Graphics::Gdi::{
 use super::{BackBuffer, PlatformApi};
 use crate::WindowDrawTarget;
 
+#[derive(Default)]
 pub struct Win32PlatformImpl;
 impl PlatformApi for Win32PlatformImpl {
     type BackBuffer = Win32BackBuffer;
     fn present_backbuffer(
+        &mut self,
         window: &impl WindowDrawTarget,
         back_buffer: Self::BackBuffer,
+        area: Option<IRect>,
     ) -> anyhow::Result<()> {
         let (hwnd, _) = get_hwnd_and_instance(&window);
         let (window_width, window_height) = window.get_draw_bounds();
 
+        // While the window is being resized the back buffer is stretched, which only
+        // works for the whole buffer.
+        let is_stretched =
+            (window_width as i32, window_height as i32) != (back_buffer.width, back_buffer.height);
+        let full = IRect::from_wh(back_buffer.width, back_buffer.height);
+        let area = match area {
+            Some(area) if !is_stretched => IRect::intersect(&area, &full).unwrap_or_default(),
+            _ => full,
+        };
+        if area.is_empty() {
+            return Ok(());
+        }
+        let (dst_width, dst_height) = if is_stretched {
+            (window_width as i32, window_height as i32)
+        } else {
+            (area.width(), area.height())
+        };
+
         let mut bitmapinfo = BITMAPINFO::default();
         bitmapinfo.bmiHeader = BITMAPINFOHEADER {
             biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
-            biWidth: back_buffer.width,
+            // Rows can be longer than the surface is wide, see `RasterStore`.
+            biWidth: (back_buffer.stride / 4) as i32,
             biHeight: back_buffer.height,
             biPlanes: 1,
             biBitCount: back_buffer.bytes_per_pixel, //32,
@@ -38,14 +61,15 @@ impl PlatformApi for Win32PlatformImpl {
         let scan_lines = unsafe {
             StretchDIBits(
                 hdc,
-                0,
-                0,
-                window_width as i32,
-                window_height as i32,
-                0,
-                0,
-                back_buffer.width,
-                back_buffer.height,
+                area.left,
+                area.top,
+                dst_width,
+                dst_height,
+                area.left,
+                // The DIB is bottom-up, source rows are counted from the bottom.
+                back_buffer.height - area.bottom,
+                area.width(),
+                area.height(),
                 back_buffer.data as *const _,
                 &bitmapinfo as *const BITMAPINFO,
                 DIB_RGB_COLORS,
@@ -63,6 +87,7 @@ impl PlatformApi for Win32PlatformImpl {
 pub struct Win32BackBuffer {
     pub width: i32,
     pub height: i32,
+    pub stride: usize,
     pub bytes_per_pixel: u16,
     pub data: *const u8,
 }
@@ -79,6 +104,7 @@ impl BackBuffer for Win32BackBuffer {
         Ok(Win32BackBuffer {
             width,
             height,
+            stride: tlp.row_bytes,
             bytes_per_pixel: 32,
             data: tlp.pixels.as_ptr() as *const _,
         })
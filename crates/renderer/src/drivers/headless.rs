@@ -0,0 +1,108 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{Frame, WindowDrawTarget};
+
+use super::{cpu::RasterStore, Driver};
+use skia::{IRect, ISize};
+use utilities::tracing;
+
+/// A driver that never touches the native window, presenting copies the surface
+/// into a [`FrameStore`] instead of blitting it to the screen.
+pub struct Headless(pub(crate) RasterStore, pub(crate) FrameStore);
+
+impl Headless {
+    pub fn new(size: impl Into<ISize>, frames: FrameStore) -> anyhow::Result<Self> {
+        Ok(Self(RasterStore::new(size)?, frames))
+    }
+
+    /// Pushes the surface into the frame store, all presenting does for this driver.
+    pub(crate) fn record_frame(&mut self) -> anyhow::Result<()> {
+        tracing::debug_span!("Headless::record_frame");
+        let size = self.0.size();
+        let frame = Frame::read_from(&mut self.0.surface, size)?;
+        self.1.push(frame);
+        Ok(())
+    }
+}
+
+impl Driver for Headless {
+    fn surface(&mut self) -> &mut skia::Surface {
+        &mut self.0.surface
+    }
+    fn size(&self) -> ISize {
+        self.0.size()
+    }
+    fn resize_surface(&mut self, _: &impl WindowDrawTarget, size: ISize) -> anyhow::Result<()> {
+        self.0.resize(size)
+    }
+    fn present_surface(
+        &mut self,
+        _: &impl WindowDrawTarget,
+        // Frames always hold the whole surface.
+        _: Option<IRect>,
+    ) -> anyhow::Result<()> {
+        self.record_frame()
+    }
+}
+
+/// A shared, bounded history of the frames presented by a headless surface.
+///
+/// Cloning a `FrameStore` gives another handle to the same frames, so a test can keep
+/// one while the surface that records into it lives in the ECS world.
+#[derive(Debug, Clone)]
+pub struct FrameStore(Arc<Mutex<FrameStoreInner>>);
+
+#[derive(Debug)]
+struct FrameStoreInner {
+    capacity: usize,
+    presented: u64,
+    frames: VecDeque<Frame>,
+}
+
+impl FrameStore {
+    /// Creates a store which keeps the last `capacity` frames, `capacity` is at least one.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(FrameStoreInner {
+            capacity: capacity.max(1),
+            presented: 0,
+            frames: VecDeque::new(),
+        })))
+    }
+
+    pub fn latest(&self) -> Option<Frame> {
+        self.0.lock().unwrap().frames.back().cloned()
+    }
+
+    /// The retained frames, oldest first.
+    pub fn frames(&self) -> Vec<Frame> {
+        self.0.lock().unwrap().frames.iter().cloned().collect()
+    }
+
+    /// How many frames have been presented in total, including ones no longer retained.
+    pub fn presented_count(&self) -> u64 {
+        self.0.lock().unwrap().presented
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().frames.clear();
+    }
+
+    pub(crate) fn push(&self, mut frame: Frame) {
+        let mut inner = self.0.lock().unwrap();
+        frame.index = inner.presented;
+        inner.presented += 1;
+        if inner.frames.len() == inner.capacity {
+            inner.frames.pop_front();
+        }
+        inner.frames.push_back(frame);
+    }
+}
+
+impl Default for FrameStore {
+    fn default() -> Self {
+        Self::with_capacity(1)
+    }
+}
@@ -1,45 +1,205 @@
 pub mod cpu;
-// mod vulkan;
-// mod vulkanold;
+pub mod headless;
+pub mod vulkan;
 
 use crate::WindowDrawTarget;
-use skia::ISize;
+use skia::{IRect, ISize};
 use utilities::tracing;
 
-use self::cpu::Cpu;
+/// Which driver a [`Surface`](crate::Surface) draws with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DriverKind {
+    Cpu,
+    Headless,
+    Vulkan,
+}
+
+/// Inserted as a resource, decides which driver new window surfaces are created with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DriverPreference {
+    /// Tries every GPU driver and falls back to the CPU when none of them work.
+    Gpu,
+    Cpu,
+    /// Vulkan without any fallback, creating the surface fails when it is unavailable.
+    Vulkan,
+}
+
+impl Default for DriverPreference {
+    fn default() -> Self {
+        DriverPreference::Gpu
+    }
+}
 
 pub enum SkiaDriver {
     Cpu(cpu::Cpu),
+    Headless(headless::Headless),
+    Vulkan(vulkan::Vulkan),
 }
 
 impl SkiaDriver {
+    pub fn new(
+        target: &impl WindowDrawTarget,
+        preference: DriverPreference,
+    ) -> anyhow::Result<Self> {
+        tracing::debug_span!("SkiaDriver::new");
+        match preference {
+            DriverPreference::Cpu => Self::new_cpu(target),
+            DriverPreference::Vulkan => Self::new_vulkan(target),
+            DriverPreference::Gpu => Self::new_vulkan(target).or_else(|err| {
+                tracing::warn!("Vulkan is unavailable, falling back to the CPU: {}", err);
+                Self::new_cpu(target)
+            }),
+        }
+    }
+
+    pub fn kind(&self) -> DriverKind {
+        match self {
+            SkiaDriver::Cpu(_) => DriverKind::Cpu,
+            SkiaDriver::Headless(_) => DriverKind::Headless,
+            SkiaDriver::Vulkan(_) => DriverKind::Vulkan,
+        }
+    }
+
     #[inline(always)]
     pub fn new_cpu(target: &impl WindowDrawTarget) -> anyhow::Result<Self> {
         tracing::debug_span!("SkiaDriver::new_cpu");
-        let mut cpu_driver = cpu::Cpu::default();
-        let bounds = target.get_draw_bounds();
-        let mut surface = cpu_driver.create_surface(target, (bounds.0 as i32, bounds.1 as i32))?;
-        surface.canvas().clear(skia::Color::CYAN);
-        Ok(SkiaDriver::Cpu(Cpu(surface)))
+        let mut cpu_driver = cpu::Cpu::new(draw_size(target))?;
+        cpu_driver.surface().canvas().clear(skia::Color::CYAN);
+        Ok(SkiaDriver::Cpu(cpu_driver))
+    }
+
+    #[inline(always)]
+    pub fn new_vulkan(target: &impl WindowDrawTarget) -> anyhow::Result<Self> {
+        tracing::debug_span!("SkiaDriver::new_vulkan");
+        let mut vulkan_driver = vulkan::Vulkan::new(target, draw_size(target))?;
+        vulkan_driver.surface().canvas().clear(skia::Color::CYAN);
+        Ok(SkiaDriver::Vulkan(vulkan_driver))
+    }
+
+    /// A CPU driver drawing into a surface of `size` that is never presented to a window.
+    #[inline(always)]
+    pub fn new_offscreen(size: impl Into<ISize>) -> anyhow::Result<Self> {
+        tracing::debug_span!("SkiaDriver::new_offscreen");
+        Ok(SkiaDriver::Cpu(cpu::Cpu::new(size)?))
+    }
+
+    /// Needs no window, the surface is only ever presented into `frames`.
+    #[inline(always)]
+    pub fn new_headless(
+        size: impl Into<ISize>,
+        frames: headless::FrameStore,
+    ) -> anyhow::Result<Self> {
+        tracing::debug_span!("SkiaDriver::new_headless");
+        let mut headless_driver = headless::Headless::new(size, frames)?;
+        headless_driver.surface().canvas().clear(skia::Color::CYAN);
+        Ok(SkiaDriver::Headless(headless_driver))
+    }
+
+    #[inline(always)]
+    pub fn canvas(&mut self) -> &mut skia::Canvas {
+        self.surface_mut().canvas()
+    }
+
+    #[inline(always)]
+    pub fn surface_mut(&mut self) -> &mut skia::Surface {
+        match self {
+            SkiaDriver::Cpu(cpu) => cpu.surface(),
+            SkiaDriver::Headless(headless) => headless.surface(),
+            SkiaDriver::Vulkan(vulkan) => vulkan.surface(),
+        }
+    }
+
+    /// The size of the drawable part of the surface in physical pixels, the skia surface
+    /// itself can be larger.
+    #[inline(always)]
+    pub fn size(&self) -> ISize {
+        match self {
+            SkiaDriver::Cpu(cpu) => cpu.size(),
+            SkiaDriver::Headless(headless) => headless.size(),
+            SkiaDriver::Vulkan(vulkan) => vulkan.size(),
+        }
+    }
+
+    /// Fits the surface to the current size of `target`, does nothing when it already does.
+    #[inline(always)]
+    pub fn resize_surface(&mut self, target: &impl WindowDrawTarget) -> anyhow::Result<()> {
+        tracing::debug_span!("SkiaDriver::resize_surface");
+        let size = draw_size(target);
+        if self.size() == size {
+            return Ok(());
+        }
+        match self {
+            SkiaDriver::Cpu(cpu) => cpu.resize_surface(target, size),
+            SkiaDriver::Headless(headless) => headless.resize_surface(target, size),
+            SkiaDriver::Vulkan(vulkan) => vulkan.resize_surface(target, size),
+        }
+    }
+
+    /// Resizes a headless surface to `size`, which has no window to take it from.
+    #[inline(always)]
+    pub fn resize_headless(&mut self, size: ISize) -> anyhow::Result<()> {
+        tracing::debug_span!("SkiaDriver::resize_headless");
+        match self {
+            SkiaDriver::Headless(headless) if headless.size() == size => Ok(()),
+            SkiaDriver::Headless(headless) => headless.0.resize(size),
+            _ => Err(anyhow::Error::msg("Not a headless surface")),
+        }
     }
 
     #[inline(always)]
-    pub fn present_surface(&mut self, target: &impl WindowDrawTarget) -> anyhow::Result<()> {
+    pub fn present_surface(
+        &mut self,
+        target: &impl WindowDrawTarget,
+        area: Option<IRect>,
+    ) -> anyhow::Result<()> {
         match self {
-            SkiaDriver::Cpu(cpu) => cpu.present_surface(target, &mut cpu.0.clone()),
+            SkiaDriver::Cpu(cpu) => cpu.present_surface(target, area),
+            SkiaDriver::Headless(headless) => headless.present_surface(target, area),
+            SkiaDriver::Vulkan(vulkan) => vulkan.present_surface(target, area),
         }
     }
 }
 
+fn draw_size(target: &impl WindowDrawTarget) -> ISize {
+    let (width, height) = target.get_draw_bounds();
+    ISize::new(width as i32, height as i32)
+}
+
+/// Backing stores are allocated in steps of this many pixels, so dragging a window border
+/// only reallocates once per step instead of on every resize.
+const SIZE_CLASS_STEP: i32 = 256;
+
+/// The size of the backing store a surface of `size` is allocated with.
+pub(crate) fn size_class(size: ISize) -> ISize {
+    let round_up =
+        |length: i32| (length.max(1) + SIZE_CLASS_STEP - 1) / SIZE_CLASS_STEP * SIZE_CLASS_STEP;
+    ISize::new(round_up(size.width), round_up(size.height))
+}
+
+/// Whether a backing store of `capacity` can keep holding a surface of `size`. Stores more
+/// than twice as large as `size` needs are reallocated to give the memory back.
+pub(crate) fn fits_size_class(capacity: ISize, size: ISize) -> bool {
+    let class = size_class(size);
+    class.width <= capacity.width
+        && class.height <= capacity.height
+        && capacity.width <= class.width * 2
+        && capacity.height <= class.height * 2
+}
+
+/// Each driver owns the surface it draws into, which keeps its contents between presents.
 pub trait Driver {
-    fn create_surface(
-        &mut self,
-        window: &impl WindowDrawTarget,
-        dimensions: impl Into<ISize>,
-    ) -> anyhow::Result<skia::Surface>;
+    fn surface(&mut self) -> &mut skia::Surface;
+    /// The drawable part of [`Driver::surface`], anything outside of it is never presented.
+    fn size(&self) -> ISize;
+    /// Makes `size` of the backing store drawable, keeping what was already drawn in the
+    /// top left corner. The backing store is only reallocated when `size` no longer fits
+    /// its size class.
+    fn resize_surface(&mut self, window: &impl WindowDrawTarget, size: ISize)
+        -> anyhow::Result<()>;
+    /// Presents `area` of the surface, the whole surface when `None`.
     fn present_surface(
         &mut self,
         window: &impl WindowDrawTarget,
-        surface: &mut skia::Surface,
+        area: Option<IRect>,
     ) -> anyhow::Result<()>;
 }
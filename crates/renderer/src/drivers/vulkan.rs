@@ -1,83 +1,94 @@
 use anyhow::Error;
 use ash::{
-    extensions::khr::{self, Swapchain},
-    vk::{
-        self, ColorSpaceKHR, Extent2D, Format, Handle, ImageUsageFlags, PhysicalDevice,
-        PresentModeKHR, SurfaceKHR, SwapchainCreateInfoKHR, SwapchainKHR,
-    },
+    extensions::khr,
+    vk::{self, Handle},
     Device, Entry, Instance,
 };
-use raw_window_handle::HasRawWindowHandle;
 use skia::{
     gpu::{self, DirectContext},
-    Budgeted, ImageInfo,
+    Budgeted, ColorType, IRect, ISize, ImageInfo,
 };
-use std::{ffi::CString, os::raw::c_char, ptr};
-
-use super::{Driver, DriverKind};
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    ptr,
+};
+use utilities::tracing;
 
-fn str_iter_collect(strs: impl Iterator<Item = &'static str>) -> Vec<*const c_char> {
-    strs.map(|s: &'static str| s.as_ptr() as *const c_char)
-        .collect::<Vec<_>>()
-}
+use super::Driver;
+use crate::WindowDrawTarget;
 
-struct Vulkan {
-    api: AshApi,
-    context: DirectContext,
-}
+/// Draws with Skia on the GPU through Vulkan.
+///
+/// Drawing goes into an offscreen render target so the contents survive between
+/// frames like they do for the CPU driver, presenting copies it into the next
+/// swapchain image. The render target is allocated for the size class of the
+/// window, only the top left part of it that fits the window is drawn to and
+/// presented. Any conformant implementation works, including software ones
+/// such as lavapipe (select it with `VK_ICD_FILENAMES`).
+pub struct Vulkan(
+    pub(crate) skia::Surface,
+    // The fields are dropped in order, every skia object has to go before the
+    // context and the context before the device it was created on.
+    Option<SwapchainState>,
+    DirectContext,
+    AshApi,
+    // The drawable size of the render target.
+    ISize,
+);
 
 impl Vulkan {
-    fn new() -> anyhow::Result<Self> {
-        let api = AshApi::new()?;
-        let context = Self::create_backend_context(&api);
-        Ok(Self { api, context })
+    pub fn new(window: &impl WindowDrawTarget, size: impl Into<ISize>) -> anyhow::Result<Self> {
+        tracing::debug_span!("Vulkan::new");
+        let size = size.into();
+        let api = AshApi::new(window)?;
+        let mut context = Self::create_backend_context(&api)?;
+        let surface = Self::create_render_target(&mut context, super::size_class(size))?;
+        let mut vulkan = Self(surface, None, context, api, size);
+        vulkan.recreate_swapchain(window.get_draw_bounds())?;
+        Ok(vulkan)
     }
-    fn create_backend_context(api: &AshApi) -> DirectContext {
+
+    fn create_backend_context(api: &AshApi) -> anyhow::Result<DirectContext> {
         let get_proc = |of| unsafe {
             match api.get_proc(of) {
                 Some(f) => f as _,
                 None => {
-                    println!("resolve of {} failed", of.name().to_str().unwrap());
+                    tracing::warn!("resolve of {} failed", of.name().to_str().unwrap());
                     ptr::null()
                 }
             }
         };
+        let instance_extensions = api
+            .instance_extensions
+            .iter()
+            .map(|name| name.to_str().unwrap())
+            .collect::<Vec<_>>();
+        let device_extensions = [khr::Swapchain::name().to_str().unwrap()];
         let backend_context = unsafe {
-            let exts: Vec<&str> = AshApi::get_required_windowing_extensions().collect();
             gpu::vk::BackendContext::new_with_extensions(
                 api.instance.handle().as_raw() as _,
                 api.physical_device.as_raw() as _,
                 api.device.handle().as_raw() as _,
-                (api.queue_and_index.0.as_raw() as _, api.queue_and_index.1),
+                (api.queue.as_raw() as _, api.queue_family_index as usize),
                 &get_proc,
-                &exts,
-                &[],
+                &instance_extensions,
+                &device_extensions,
             )
         };
-        DirectContext::new_vulkan(&backend_context, None).unwrap()
+        DirectContext::new_vulkan(&backend_context, None).map_or_else(
+            || Err(Error::msg("Unable to create a Vulkan DirectContext")),
+            |e| Ok(e),
+        )
     }
-}
 
-impl Driver for Vulkan {
-    const DRIVER: DriverKind = DriverKind::Vulkan;
-    fn create_surface(
-        &mut self,
-        window: impl HasRawWindowHandle,
-        size: impl Into<skia::ISize>,
+    fn create_render_target(
+        context: &mut DirectContext,
+        capacity: ISize,
     ) -> anyhow::Result<skia::Surface> {
-        let size = size.into();
-        let surface = unsafe {
-            ash_window::create_surface(&self.api.entry, &self.api.instance, &window, None)?
-        };
-        let surface_fn = ash::extensions::khr::Surface::new(&self.api.entry, &self.api.instance);
-        let swapchain_handle = self
-            .api
-            .create_swapchain(surface, (size.width as u32, size.height as u32))?;
-
-        let image_info = ImageInfo::new_n32_premul(size, None);
-
-        Ok(skia::Surface::new_render_target(
-            &mut self.context,
+        let image_info = ImageInfo::new_n32_premul(capacity, None);
+        skia::Surface::new_render_target(
+            context,
             Budgeted::Yes,
             &image_info,
             None,
@@ -85,76 +96,378 @@ impl Driver for Vulkan {
             None,
             false,
         )
-        .unwrap())
+        .map_or_else(|| Err(Error::msg("Unable to create Surface")), |e| Ok(e))
+    }
+
+    /// Recreates the swapchain, e.g. after the window was resized.
+    fn recreate_swapchain(&mut self, (width, height): (u32, u32)) -> anyhow::Result<()> {
+        let old = self.1.take();
+        let old_swapchain = old
+            .as_ref()
+            .map_or(vk::SwapchainKHR::null(), |old| old.swapchain);
+        let swapchain = SwapchainState::new(&self.3, &mut self.2, (width, height), old_swapchain);
+        drop(old);
+        self.1 = Some(swapchain?);
+        Ok(())
+    }
+}
+
+impl Driver for Vulkan {
+    fn surface(&mut self) -> &mut skia::Surface {
+        &mut self.0
+    }
+
+    fn size(&self) -> ISize {
+        self.4
+    }
+
+    fn resize_surface(
+        &mut self,
+        window: &impl WindowDrawTarget,
+        size: ISize,
+    ) -> anyhow::Result<()> {
+        let capacity = ISize::new(self.0.width(), self.0.height());
+        if !super::fits_size_class(capacity, size) {
+            let mut surface = Self::create_render_target(&mut self.2, super::size_class(size))?;
+            self.0.draw(
+                surface.canvas(),
+                (0, 0),
+                skia::SamplingOptions::default(),
+                None,
+            );
+            self.0 = surface;
+        }
+        self.4 = size;
+        self.recreate_swapchain(window.get_draw_bounds())
+    }
+
+    fn present_surface(
+        &mut self,
+        window: &impl WindowDrawTarget,
+        // Swapchain images are always presented whole.
+        _: Option<IRect>,
+    ) -> anyhow::Result<()> {
+        tracing::debug_span!("Vulkan::present_surface");
+        let bounds = window.get_draw_bounds();
+        if bounds.0 == 0 || bounds.1 == 0 {
+            return Ok(());
+        }
+
+        let index = match self.1.as_mut().unwrap().acquire(&self.3) {
+            Ok(Some(index)) => index,
+            // The swapchain no longer matches the window, try again with a new one.
+            Ok(None) | Err(_) => {
+                self.recreate_swapchain(bounds)?;
+                match self.1.as_mut().unwrap().acquire(&self.3)? {
+                    Some(index) => index,
+                    None => return Err(Error::msg("Unable to acquire a swapchain image")),
+                }
+            }
+        };
+
+        let swapchain = self.1.as_mut().unwrap();
+        let target = &mut swapchain.surfaces[index as usize];
+        let image = self
+            .0
+            .image_snapshot_with_bounds(IRect::from_size(self.4))
+            .map_or_else(
+                || Err(Error::msg("Unable to snapshot the Surface")),
+                |e| Ok(e),
+            )?;
+        target.canvas().clear(skia::Color::BLACK);
+        target.canvas().draw_image(&image, (0, 0), None);
+        // Hands the image back in the PRESENT_SRC layout.
+        target.flush_with_access_info(
+            skia::surface::BackendSurfaceAccess::Present,
+            &gpu::FlushInfo::default(),
+        );
+        // Waiting for the GPU here keeps presenting free of semaphores.
+        self.2.submit(true);
+
+        let is_suboptimal = swapchain.present(&self.3, index);
+        match is_suboptimal {
+            Ok(false) => Ok(()),
+            Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.recreate_swapchain(bounds),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// The swapchain of one window surface along with a skia surface wrapping each image.
+struct SwapchainState {
+    surfaces: Vec<skia::Surface>,
+    swapchain: vk::SwapchainKHR,
+    swapchain_fn: khr::Swapchain,
+    acquired: vk::Fence,
+    device: Device,
+}
+
+impl SwapchainState {
+    fn new(
+        api: &AshApi,
+        context: &mut DirectContext,
+        (width, height): (u32, u32),
+        old_swapchain: vk::SwapchainKHR,
+    ) -> anyhow::Result<Self> {
+        tracing::debug_span!("SwapchainState::new");
+        let capabilities = unsafe {
+            api.surface_fn
+                .get_physical_device_surface_capabilities(api.physical_device, api.surface)?
+        };
+        let formats = unsafe {
+            api.surface_fn
+                .get_physical_device_surface_formats(api.physical_device, api.surface)?
+        };
+        let (format, color_type) = match formats.iter().find_map(|format| match format.format {
+            vk::Format::B8G8R8A8_UNORM => Some((*format, ColorType::BGRA8888)),
+            vk::Format::R8G8B8A8_UNORM => Some((*format, ColorType::RGBA8888)),
+            _ => None,
+        }) {
+            Some(format) => format,
+            None => return Err(Error::msg("No supported swapchain format")),
+        };
+
+        // u32::MAX means the surface takes its size from the swapchain.
+        let extent = if capabilities.current_extent.width != u32::MAX {
+            capabilities.current_extent
+        } else {
+            vk::Extent2D {
+                width: width.clamp(
+                    capabilities.min_image_extent.width,
+                    capabilities.max_image_extent.width,
+                ),
+                height: height.clamp(
+                    capabilities.min_image_extent.height,
+                    capabilities.max_image_extent.height,
+                ),
+            }
+        };
+        let mut image_count = capabilities.min_image_count + 1;
+        if capabilities.max_image_count != 0 {
+            image_count = image_count.min(capabilities.max_image_count);
+        }
+        let composite_alpha = if capabilities
+            .supported_composite_alpha
+            .contains(vk::CompositeAlphaFlagsKHR::OPAQUE)
+        {
+            vk::CompositeAlphaFlagsKHR::OPAQUE
+        } else {
+            vk::CompositeAlphaFlagsKHR::INHERIT
+        };
+
+        let swapchain_fn = khr::Swapchain::new(&api.instance, &api.device);
+        let create_info = vk::SwapchainCreateInfoKHR::builder()
+            .surface(api.surface)
+            .min_image_count(image_count)
+            .image_format(format.format)
+            .image_color_space(format.color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
+            .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .pre_transform(capabilities.current_transform)
+            .composite_alpha(composite_alpha)
+            // FIFO is the only mode every implementation has to support.
+            .present_mode(vk::PresentModeKHR::FIFO)
+            .clipped(true)
+            .old_swapchain(old_swapchain);
+        let swapchain = unsafe { swapchain_fn.create_swapchain(&create_info, None)? };
+        let images = unsafe { swapchain_fn.get_swapchain_images(swapchain)? };
+        let acquired = unsafe {
+            api.device
+                .create_fence(&vk::FenceCreateInfo::default(), None)?
+        };
+
+        let skia_format = match format.format {
+            vk::Format::B8G8R8A8_UNORM => gpu::vk::Format::B8G8R8A8_UNORM,
+            _ => gpu::vk::Format::R8G8B8A8_UNORM,
+        };
+        let mut surfaces = Vec::with_capacity(images.len());
+        for image in images {
+            let image_info = unsafe {
+                gpu::vk::ImageInfo::new(
+                    image.as_raw() as _,
+                    gpu::vk::Alloc::default(),
+                    gpu::vk::ImageTiling::OPTIMAL,
+                    gpu::vk::ImageLayout::UNDEFINED,
+                    skia_format,
+                    1,
+                    api.queue_family_index,
+                    None,
+                    None,
+                    None,
+                )
+            };
+            let render_target = gpu::BackendRenderTarget::new_vulkan(
+                (extent.width as i32, extent.height as i32),
+                0,
+                &image_info,
+            );
+            match skia::Surface::from_backend_render_target(
+                context,
+                &render_target,
+                gpu::SurfaceOrigin::TopLeft,
+                color_type,
+                None,
+                None,
+            ) {
+                Some(surface) => surfaces.push(surface),
+                None => {
+                    unsafe {
+                        api.device.destroy_fence(acquired, None);
+                        swapchain_fn.destroy_swapchain(swapchain, None);
+                    }
+                    return Err(Error::msg("Unable to wrap a swapchain image"));
+                }
+            }
+        }
+
+        Ok(Self {
+            surfaces,
+            swapchain,
+            swapchain_fn,
+            acquired,
+            device: api.device.clone(),
+        })
     }
-    fn present_surface(&mut self) {
-        todo!()
+
+    /// Waits for the next image to be free, `None` when the swapchain is out of date.
+    fn acquire(&mut self, api: &AshApi) -> anyhow::Result<Option<u32>> {
+        let result = unsafe {
+            self.swapchain_fn.acquire_next_image(
+                self.swapchain,
+                u64::MAX,
+                vk::Semaphore::null(),
+                self.acquired,
+            )
+        };
+        let index = match result {
+            Ok((index, _)) => index,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        unsafe {
+            api.device
+                .wait_for_fences(&[self.acquired], true, u64::MAX)?;
+            api.device.reset_fences(&[self.acquired])?;
+        }
+        Ok(Some(index))
+    }
+
+    /// Returns whether the swapchain should be recreated.
+    fn present(&mut self, api: &AshApi, index: u32) -> Result<bool, vk::Result> {
+        let swapchains = [self.swapchain];
+        let indices = [index];
+        let present_info = vk::PresentInfoKHR::builder()
+            .swapchains(&swapchains)
+            .image_indices(&indices);
+        unsafe { self.swapchain_fn.queue_present(api.queue, &present_info) }
+    }
+}
+
+impl Drop for SwapchainState {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.device.device_wait_idle();
+            self.surfaces.clear();
+            self.device.destroy_fence(self.acquired, None);
+            self.swapchain_fn.destroy_swapchain(self.swapchain, None);
+        }
     }
 }
 
 pub struct AshApi {
-    pub(crate) queue_and_index: (vk::Queue, usize),
-    pub(crate) device: ash::Device,
+    pub(crate) queue: vk::Queue,
+    pub(crate) queue_family_index: u32,
+    pub(crate) device: Device,
     pub(crate) physical_device: vk::PhysicalDevice,
+    pub(crate) surface: vk::SurfaceKHR,
+    pub(crate) surface_fn: khr::Surface,
+    pub(crate) instance_extensions: Vec<&'static CStr>,
     pub(crate) instance: Instance,
     pub(crate) entry: Entry,
 }
 
 impl Drop for AshApi {
     fn drop(&mut self) {
-        // SAFETY: Explicit drop ordering
-        drop(self.queue_and_index);
-        drop(self.device);
-        drop(self.physical_device);
-        drop(self.instance);
-        drop(self.entry);
+        unsafe {
+            let _ = self.device.device_wait_idle();
+            self.device.destroy_device(None);
+            self.surface_fn.destroy_surface(self.surface, None);
+            self.instance.destroy_instance(None);
+        }
     }
 }
 
 impl AshApi {
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(window: &impl WindowDrawTarget) -> anyhow::Result<Self> {
         let entry = unsafe { Entry::load()? };
-        let instance = Self::create_instance(&entry)?;
-        let (physical_device, queue_family_index) = Self::create_physical_device(&instance)?;
-        let device = Self::create_logical_device(instance, physical_device, queue_family_index)?;
+        let instance_extensions = ash_window::enumerate_required_extensions(window)?
+            .iter()
+            .map(|&name| unsafe { CStr::from_ptr(name) })
+            .collect::<Vec<_>>();
+        let instance = Self::create_instance(&entry, &instance_extensions)?;
+
+        let surface_fn = khr::Surface::new(&entry, &instance);
+        let surface = match unsafe { ash_window::create_surface(&entry, &instance, window, None) } {
+            Ok(surface) => surface,
+            Err(err) => {
+                unsafe { instance.destroy_instance(None) };
+                return Err(err.into());
+            }
+        };
+
+        let device = Self::create_physical_device(&instance, &surface_fn, surface).and_then(
+            |(physical_device, queue_family_index)| {
+                let device =
+                    Self::create_logical_device(&instance, physical_device, queue_family_index)?;
+                Ok((physical_device, queue_family_index, device))
+            },
+        );
+        let (physical_device, queue_family_index, device) = match device {
+            Ok(device) => device,
+            Err(err) => {
+                unsafe {
+                    surface_fn.destroy_surface(surface, None);
+                    instance.destroy_instance(None);
+                }
+                return Err(err);
+            }
+        };
 
         let physical_device_properties =
             unsafe { instance.get_physical_device_properties(physical_device) };
-        let device_name = unsafe {
-            std::str::from_utf8_unchecked(std::mem::transmute(
-                physical_device_properties.device_name,
-            ))
-        };
-        println!("Device Name: {}", device_name);
+        let device_name =
+            unsafe { CStr::from_ptr(physical_device_properties.device_name.as_ptr()) };
+        tracing::info!("Vulkan device: {}", device_name.to_string_lossy());
 
-        let queue_index: usize = 0;
-        let queue: vk::Queue =
-            unsafe { device.get_device_queue(queue_family_index as _, queue_index as _) };
+        let queue = unsafe { device.get_device_queue(queue_family_index, 0) };
 
         Ok(Self {
-            queue_and_index: (queue, queue_index),
+            queue,
+            queue_family_index,
             device,
             physical_device,
+            surface,
+            surface_fn,
+            instance_extensions,
             instance,
             entry,
         })
     }
 
     pub fn create_logical_device(
-        instance: Instance,
-        physical_device: PhysicalDevice,
-        queue_family_index: usize,
+        instance: &Instance,
+        physical_device: vk::PhysicalDevice,
+        queue_family_index: u32,
     ) -> anyhow::Result<Device> {
-        let extension_names =
-            Self::get_required_windowing_extensions().chain(["VK_KHR_swapchain"].into_iter());
-            
         let features = vk::PhysicalDeviceFeatures::default();
         let priorities = [1.0];
         let queue_info = [vk::DeviceQueueCreateInfo::builder()
-            .queue_family_index(queue_family_index as _)
+            .queue_family_index(queue_family_index)
             .queue_priorities(&priorities)
             .build()];
-        let device_extension_names_raw = [];
+        let device_extension_names_raw = [khr::Swapchain::name().as_ptr()];
         let device_create_info = vk::DeviceCreateInfo::builder()
             .queue_create_infos(&queue_info)
             .enabled_extension_names(&device_extension_names_raw)
@@ -163,20 +476,31 @@ impl AshApi {
         unsafe { Ok(instance.create_device(physical_device, &device_create_info, None)?) }
     }
 
-    pub fn create_physical_device(instance: &Instance) -> anyhow::Result<(PhysicalDevice, usize)> {
-        let physical_devices = unsafe { instance.enumerate_physical_devices() }
-            .expect("Failed to enumerate Vulkan physical devices.");
+    /// Picks the first device with a queue family that can both draw and present to `surface`.
+    pub fn create_physical_device(
+        instance: &Instance,
+        surface_fn: &khr::Surface,
+        surface: vk::SurfaceKHR,
+    ) -> anyhow::Result<(vk::PhysicalDevice, u32)> {
+        let physical_devices = unsafe { instance.enumerate_physical_devices()? };
         match physical_devices
             .iter()
-            .map(|physical_device: &PhysicalDevice| {
-                // TODO: look at the Flutter examples to see how to properly choose a valid device
+            .map(|physical_device: &vk::PhysicalDevice| {
                 unsafe { instance.get_physical_device_queue_family_properties(*physical_device) }
                     .iter()
                     .enumerate()
                     .find_map(|(index, info)| {
                         let supports_graphic = info.queue_flags.contains(vk::QueueFlags::GRAPHICS);
-                        if supports_graphic {
-                            Some((*physical_device, index))
+                        let supports_present = unsafe {
+                            surface_fn.get_physical_device_surface_support(
+                                *physical_device,
+                                index as u32,
+                                surface,
+                            )
+                        }
+                        .unwrap_or(false);
+                        if supports_graphic && supports_present {
+                            Some((*physical_device, index as u32))
                         } else {
                             None
                         }
@@ -189,53 +513,49 @@ impl AshApi {
         }
     }
 
-    pub fn create_instance(entry: &Entry) -> anyhow::Result<Instance> {
-        let layer_names = [
-            "VK_LAYER_LUNARG_standard_validation",
-            "VK_LAYER_KHRONOS_validation",
-        ]
-        .into_iter();
+    pub fn create_instance(entry: &Entry, extensions: &[&CStr]) -> anyhow::Result<Instance> {
+        let api_version = Self::vulkan_version(entry)
+            .map(|(major, minor, _)| vk::make_api_version(0, major, minor, 0))
+            .unwrap_or(vk::make_api_version(0, 1, 0, 0));
+
+        // Validation is only turned on for debug builds, and only when it is installed.
+        let validation = CString::new("VK_LAYER_KHRONOS_validation").unwrap();
+        let mut layer_names: Vec<*const c_char> = Vec::new();
+        if cfg!(debug_assertions)
+            && entry
+                .enumerate_instance_layer_properties()?
+                .iter()
+                .any(|layer| unsafe { CStr::from_ptr(layer.layer_name.as_ptr()) } == &*validation)
+        {
+            layer_names.push(validation.as_ptr());
+        }
+        let extension_names = extensions
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect::<Vec<_>>();
+
+        let application_name = CString::new("application_name").unwrap();
         let app_desc = vk::ApplicationInfo::builder()
-            .api_version(vk::make_api_version(0, 1, 0, 0))
-            .application_name(CString::new("application_name").unwrap().as_c_str())
-            .build();
+            .api_version(api_version)
+            .application_name(&application_name);
         let instance_desc = vk::InstanceCreateInfo::builder()
             .application_info(&app_desc)
-            .enabled_layer_names(&str_iter_collect(layer_names))
-            .enabled_extension_names(&[]);
+            .enabled_layer_names(&layer_names)
+            .enabled_extension_names(&extension_names);
         unsafe { Ok(entry.create_instance(&instance_desc, None)?) }
     }
 
-    pub fn create_swapchain(
-        &self,
-        surface: SurfaceKHR,
-        (width, height): (u32, u32),
-    ) -> anyhow::Result<SwapchainKHR> {
-        let swapchain = Swapchain::new(&self.instance, &self.device);
-        let create_info = SwapchainCreateInfoKHR::builder()
-            .clipped(true)
-            .surface(surface)
-            .min_image_count(1)
-            .image_format(Format::B8G8R8A8_SRGB)
-            .image_color_space(ColorSpaceKHR::SRGB_NONLINEAR)
-            .image_array_layers(1)
-            .image_usage(ImageUsageFlags::COLOR_ATTACHMENT)
-            .present_mode(PresentModeKHR::FIFO)
-            .image_extent(Extent2D { width, height });
-
-        unsafe { Ok(swapchain.create_swapchain(&create_info, None)?) }
-    }
-
-    pub fn vulkan_version(&self) -> Option<(usize, usize, usize)> {
-        let detected_version = self.entry.try_enumerate_instance_version().unwrap_or(None);
+    pub fn vulkan_version(entry: &Entry) -> Option<(u32, u32, u32)> {
+        let detected_version = entry.try_enumerate_instance_version().unwrap_or(None);
         detected_version.map(|ver| {
             (
-                vk::api_version_major(ver).try_into().unwrap(),
-                vk::api_version_minor(ver).try_into().unwrap(),
-                vk::api_version_patch(ver).try_into().unwrap(),
+                vk::api_version_major(ver),
+                vk::api_version_minor(ver),
+                vk::api_version_patch(ver),
             )
         })
     }
+
     pub unsafe fn get_proc(&self, of: gpu::vk::GetProcOf) -> Option<unsafe extern "system" fn()> {
         match of {
             gpu::vk::GetProcOf::Instance(instance, name) => {
@@ -248,80 +568,4 @@ impl AshApi {
             }
         }
     }
-    pub fn get_required_windowing_extensions() -> impl Iterator<Item = &'static str> {
-        #[cfg(target_os = "windows")]
-        {
-            const WINDOWS_EXTS: [&'static str; 2] = [
-                khr::Surface::name().to_str().unwrap(),
-                khr::Win32Surface::name().to_str().unwrap(),
-            ];
-            WINDOWS_EXTS.into_iter()
-        }
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            const WAYLAND_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::WaylandSurface::name().as_ptr(),
-            ];
-            &WAYLAND_EXTS
-        }
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            const XLIB_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::XlibSurface::name().as_ptr(),
-            ];
-            &XLIB_EXTS
-        }
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            const XCB_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::XcbSurface::name().as_ptr(),
-            ];
-            &XCB_EXTS
-        }
-        #[cfg(any(target_os = "android"))]
-        {
-            const ANDROID_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::AndroidSurface::name().as_ptr(),
-            ];
-            &ANDROID_EXTS
-        }
-        #[cfg(any(target_os = "macos"))]
-        {
-            const MACOS_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                ext::MetalSurface::name().as_ptr(),
-            ];
-            &MACOS_EXTS
-        }
-        #[cfg(any(target_os = "ios"))]
-        {
-            const IOS_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                ext::MetalSurface::name().as_ptr(),
-            ];
-            &IOS_EXTS
-        }
-    }
 }
@@ -1,316 +0,0 @@
-// George: Most of this file is  copied from the skia-safe exmaples.
-
-use std::{
-    ffi::CString,
-    os::raw::{self, c_char},
-    ptr,
-};
-
-use ash::{
-    extensions::khr,
-    vk::{self, Handle},
-    Entry, Instance,
-};
-use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
-use skia::{gpu, Budgeted, ISize, ImageInfo, Surface};
-
-use super::{Driver, DriverKind};
-
-pub struct Vulkan {
-    context: gpu::DirectContext,
-    ash_graphics: AshGraphics,
-}
-
-impl Vulkan {
-    pub fn new() -> Self {
-        let ash_graphics = unsafe { AshGraphics::new("skia-org") };
-        let context = {
-            let get_proc = |of| unsafe {
-                match ash_graphics.get_proc(of) {
-                    Some(f) => f as _,
-                    None => {
-                        println!("resolve of {} failed", of.name().to_str().unwrap());
-                        ptr::null()
-                    }
-                }
-            };
-            let backend_context = unsafe {
-                gpu::vk::BackendContext::new_with_extensions(
-                    ash_graphics.instance.handle().as_raw() as _,
-                    ash_graphics.physical_device.as_raw() as _,
-                    ash_graphics.device.handle().as_raw() as _,
-                    (
-                        ash_graphics.queue_and_index.0.as_raw() as _,
-                        ash_graphics.queue_and_index.1,
-                    ),
-                    &get_proc,
-                    AshGraphics::get_extensions(),
-                    &[]
-                )
-            };
-            gpu::DirectContext::new_vulkan(&backend_context, None).unwrap()
-        };
-        Self {
-            context,
-            ash_graphics,
-        }
-    }
-}
-
-impl Drop for Vulkan {
-    fn drop(&mut self) {
-        // context must be droped before ash_graphics
-        drop(&mut self.context);
-        drop(&mut self.ash_graphics);
-    }
-}
-
-impl Driver for Vulkan {
-    const DRIVER: DriverKind = DriverKind::Vulkan;
-    fn create_surface(
-        &mut self,
-        window: impl HasRawWindowHandle,
-        dimensions: impl Into<ISize>,
-    ) -> skia::Surface {
-        let image_info = ImageInfo::new_n32_premul(dimensions, None);
-        Surface::new_render_target(
-            &mut self.context,
-            Budgeted::Yes,
-            &image_info,
-            None,
-            gpu::SurfaceOrigin::TopLeft,
-            None,
-            false,
-        )
-        .expect("Failed to created surface")
-    }
-}
-
-pub struct AshGraphics {
-    pub entry: Entry,
-    pub instance: Instance,
-    pub physical_device: vk::PhysicalDevice,
-    pub device: ash::Device,
-    pub queue_and_index: (vk::Queue, usize),
-}
-
-impl Drop for AshGraphics {
-    fn drop(&mut self) {
-        unsafe {
-            self.device.device_wait_idle().unwrap();
-            self.device.destroy_device(None);
-            self.instance.destroy_instance(None);
-        }
-    }
-}
-
-// most code copied from here: https://github.com/MaikKlein/ash/blob/master/examples/src/lib.rs
-impl AshGraphics {
-    pub fn vulkan_version() -> Option<(usize, usize, usize)> {
-        let entry = unsafe { Entry::load() }.unwrap();
-
-        let detected_version = entry.try_enumerate_instance_version().unwrap_or(None);
-
-        detected_version.map(|ver| {
-            (
-                vk::api_version_major(ver).try_into().unwrap(),
-                vk::api_version_minor(ver).try_into().unwrap(),
-                vk::api_version_patch(ver).try_into().unwrap(),
-            )
-        })
-    }
-
-    pub unsafe fn new(app_name: &str) -> AshGraphics {
-        let entry = Entry::load().unwrap();
-        let instance: Instance = {
-            let api_version = Self::vulkan_version()
-                .map(|(major, minor, patch)| {
-                    vk::make_api_version(
-                        0,
-                        major.try_into().unwrap(),
-                        minor.try_into().unwrap(),
-                        patch.try_into().unwrap(),
-                    )
-                })
-                .unwrap_or(vk::make_api_version(0, 1, 0, 0));
-
-            let app_name = CString::new(app_name).unwrap();
-            let layer_names: [&CString; 0] = []; // [CString::new("VK_LAYER_LUNARG_standard_validation").unwrap()];
-            let extension_names_raw = Self::get_extensions(); // extension_names();
-
-            let app_info = vk::ApplicationInfo::builder()
-                .application_name(&app_name)
-                .application_version(0)
-                .engine_name(&app_name)
-                .engine_version(0)
-                .api_version(api_version);
-
-            let layers_names_raw: Vec<*const raw::c_char> = layer_names
-                .iter()
-                .map(|raw_name| raw_name.as_ptr())
-                .collect();
-
-            let exts = extension_names_raw
-                .iter()
-                .map(|&s: &&'static str| s.as_ptr() as *const c_char)
-                .collect::<Vec<_>>();
-
-            let create_info = vk::InstanceCreateInfo::builder()
-                .application_info(&app_info)
-                .enabled_layer_names(&layers_names_raw)
-                .enabled_extension_names(&exts);
-
-            entry
-                .create_instance(&create_info, None)
-                .expect("Failed to create a Vulkan instance.")
-        };
-
-        let (physical_device, queue_family_index) = {
-            let physical_devices = instance
-                .enumerate_physical_devices()
-                .expect("Failed to enumerate Vulkan physical devices.");
-
-            physical_devices
-                .iter()
-                .map(|physical_device| {
-                    instance
-                        .get_physical_device_queue_family_properties(*physical_device)
-                        .iter()
-                        .enumerate()
-                        .find_map(|(index, info)| {
-                            let supports_graphic =
-                                info.queue_flags.contains(vk::QueueFlags::GRAPHICS);
-                            if supports_graphic {
-                                Some((*physical_device, index))
-                            } else {
-                                None
-                            }
-                        })
-                })
-                .find_map(|v| v)
-                .expect("Failed to find a suitable Vulkan device.")
-        };
-
-        let device: ash::Device = {
-            let features = vk::PhysicalDeviceFeatures::default();
-
-            let priorities = [1.0];
-
-            let queue_info = [vk::DeviceQueueCreateInfo::builder()
-                .queue_family_index(queue_family_index as _)
-                .queue_priorities(&priorities)
-                .build()];
-
-            let device_extension_names_raw = [];
-
-            let device_create_info = vk::DeviceCreateInfo::builder()
-                .queue_create_infos(&queue_info)
-                .enabled_extension_names(&device_extension_names_raw)
-                .enabled_features(&features);
-
-            instance
-                .create_device(physical_device, &device_create_info, None)
-                .unwrap()
-        };
-
-        let queue_index: usize = 0;
-        let queue: vk::Queue = device.get_device_queue(queue_family_index as _, queue_index as _);
-
-        AshGraphics {
-            queue_and_index: (queue, queue_index),
-            device,
-            physical_device,
-            instance,
-            entry,
-        }
-    }
-
-    pub unsafe fn get_proc(&self, of: gpu::vk::GetProcOf) -> Option<unsafe extern "system" fn()> {
-        match of {
-            gpu::vk::GetProcOf::Instance(instance, name) => {
-                let ash_instance = vk::Instance::from_raw(instance as _);
-                self.entry.get_instance_proc_addr(ash_instance, name)
-            }
-            gpu::vk::GetProcOf::Device(device, name) => {
-                let ash_device = vk::Device::from_raw(device as _);
-                self.instance.get_device_proc_addr(ash_device, name)
-            }
-        }
-    }
-    pub fn get_extensions() -> &'static [&'static str] {
-        #[cfg(target_os = "windows")]
-        {
-            const WINDOWS_EXTS: [&'static str; 2] = [
-                khr::Surface::name().to_str().unwrap(),
-                khr::Win32Surface::name().to_str().unwrap(),
-            ];
-            &WINDOWS_EXTS
-        }
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            const WAYLAND_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::WaylandSurface::name().as_ptr(),
-            ];
-            &WAYLAND_EXTS
-        }
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            const XLIB_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::XlibSurface::name().as_ptr(),
-            ];
-            &XLIB_EXTS
-        }
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd"
-        ))]
-        {
-            const XCB_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::XcbSurface::name().as_ptr(),
-            ];
-            &XCB_EXTS
-        }
-        #[cfg(any(target_os = "android"))]
-        {
-            const ANDROID_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                khr::AndroidSurface::name().as_ptr(),
-            ];
-            &ANDROID_EXTS
-        }
-        #[cfg(any(target_os = "macos"))]
-        {
-            const MACOS_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                ext::MetalSurface::name().as_ptr(),
-            ];
-            &MACOS_EXTS
-        }
-        #[cfg(any(target_os = "ios"))]
-        {
-            const IOS_EXTS: [*const c_char; 2] = [
-                khr::Surface::name().as_ptr(),
-                ext::MetalSurface::name().as_ptr(),
-            ];
-            &IOS_EXTS
-        }
-    }
-}
@@ -0,0 +1,338 @@
+//! A retained scene graph drawn into a window's [`Surface`].
+//!
+//! Any entity can be a render element, it draws whichever of [`DrawShape`], [`DrawText`]
+//! and [`DrawImage`] it has. Elements are arranged in a [`RenderElementTree`], children
+//! draw on top of their parent and inherit its [`Transform`], [`Clip`] and [`Opacity`].
+
+use std::collections::HashMap;
+
+use anyhow::Error;
+use bevy_ecs::prelude::{Component, Entity, Query};
+use id_tree::{InsertBehavior, MoveBehavior, Node, NodeId, RemoveBehavior, Tree};
+use skia::{ClipOp, RRect};
+
+use crate::{
+    image::{Image, ImageStyle},
+    painter::{Brush, Path, Point, Rect},
+    text::TextLayout,
+    Painter, Surface,
+};
+
+/// Positions an element and its children relative to the parent element.
+///
+/// Applied as translate, then rotate, then scale.
+#[derive(Component, Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub translation: Point,
+    /// In degrees, clockwise.
+    pub rotation: f32,
+    pub scale: (f32, f32),
+}
+
+impl Transform {
+    pub fn from_translation(x: f32, y: f32) -> Self {
+        Self {
+            translation: Point::new(x, y),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_rotation(mut self, degrees: f32) -> Self {
+        self.rotation = degrees;
+        self
+    }
+
+    pub fn with_scale(mut self, x: f32, y: f32) -> Self {
+        self.scale = (x, y);
+        self
+    }
+
+    fn apply(&self, canvas: &mut skia::Canvas) {
+        canvas.translate(skia::Vector::from(self.translation));
+        if self.rotation != 0.0 {
+            canvas.rotate(self.rotation, None);
+        }
+        if self.scale != (1.0, 1.0) {
+            canvas.scale(self.scale);
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Point::default(),
+            rotation: 0.0,
+            scale: (1.0, 1.0),
+        }
+    }
+}
+
+/// Restricts drawing of an element and its children to a rectangle in the element's
+/// own coordinates, after its [`Transform`].
+#[derive(Component, Debug, Copy, Clone, PartialEq)]
+pub struct Clip {
+    pub rect: Rect,
+    /// Rounds the corners of the clip, 0.0 keeps them square.
+    pub radius: f32,
+}
+
+impl Clip {
+    pub fn rect(rect: impl Into<Rect>) -> Self {
+        Self {
+            rect: rect.into(),
+            radius: 0.0,
+        }
+    }
+
+    pub fn rounded(rect: impl Into<Rect>, radius: f32) -> Self {
+        Self {
+            rect: rect.into(),
+            radius,
+        }
+    }
+
+    fn apply(&self, canvas: &mut skia::Canvas) {
+        let rect = skia::Rect::from(self.rect);
+        if self.radius > 0.0 {
+            canvas.clip_rrect(
+                RRect::new_rect_xy(rect, self.radius, self.radius),
+                ClipOp::Intersect,
+                true,
+            );
+        } else {
+            canvas.clip_rect(rect, ClipOp::Intersect, true);
+        }
+    }
+}
+
+/// From 0.0 (invisible) to 1.0 (opaque), the element and its children are composited
+/// together before the opacity is applied.
+#[derive(Component, Debug, Copy, Clone, PartialEq)]
+pub struct Opacity(pub f32);
+
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Rect(Rect),
+    RoundedRect {
+        rect: Rect,
+        radius_x: f32,
+        radius_y: f32,
+    },
+    Circle {
+        center: Point,
+        radius: f32,
+    },
+    /// The ellipse inscribed in the rect.
+    Ellipse(Rect),
+    Line(Point, Point),
+    Polyline(Vec<Point>),
+    Path(Path),
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct DrawShape {
+    pub shape: Shape,
+    pub brush: Brush,
+}
+
+impl DrawShape {
+    pub fn new(shape: Shape, brush: Brush) -> Self {
+        Self { shape, brush }
+    }
+}
+
+#[derive(Component)]
+pub struct DrawText {
+    pub layout: TextLayout,
+    /// The top left corner of the layout.
+    pub position: Point,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct DrawImage {
+    pub image: Image,
+    pub dst: Rect,
+    pub style: ImageStyle,
+}
+
+/// The components read while rendering a [`RenderElementTree`].
+pub type RenderElements<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Option<&'static Transform>,
+        Option<&'static Clip>,
+        Option<&'static Opacity>,
+        Option<&'static DrawShape>,
+        Option<&'static DrawText>,
+        Option<&'static DrawImage>,
+    ),
+>;
+
+/// The render elements of one window and the order they are drawn in.
+#[derive(Component)]
+pub struct RenderElementTree {
+    tree: Tree<Entity>,
+    nodes: HashMap<Entity, NodeId>,
+}
+
+impl RenderElementTree {
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::new(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> Option<Entity> {
+        self.tree
+            .root_node_id()
+            .map(|id| *self.tree.get(id).unwrap().data())
+    }
+
+    /// Makes `entity` the root element, the previous root becomes its first child.
+    pub fn set_root(&mut self, entity: Entity) -> anyhow::Result<()> {
+        if self.contains(entity) {
+            return Err(Error::msg("Entity is already in the RenderElementTree"));
+        }
+        let id = self
+            .tree
+            .insert(Node::new(entity), InsertBehavior::AsRoot)?;
+        self.nodes.insert(entity, id);
+        Ok(())
+    }
+
+    /// Adds `child` as the last, top most, child of `parent`. A `child` already in the
+    /// tree is moved along with its children.
+    pub fn insert(&mut self, parent: Entity, child: Entity) -> anyhow::Result<()> {
+        let parent_id = match self.nodes.get(&parent) {
+            Some(id) => id.clone(),
+            None => return Err(Error::msg("Parent is not in the RenderElementTree")),
+        };
+        match self.nodes.get(&child) {
+            Some(child_id) => {
+                let child_id = child_id.clone();
+                if self
+                    .tree
+                    .ancestor_ids(&parent_id)?
+                    .any(|id| *id == child_id)
+                    || child_id == parent_id
+                {
+                    return Err(Error::msg("An element cannot be moved under itself"));
+                }
+                // Moved nodes become the last child of their new parent.
+                self.tree
+                    .move_node(&child_id, MoveBehavior::ToParent(&parent_id))?;
+            }
+            None => {
+                let id = self
+                    .tree
+                    .insert(Node::new(child), InsertBehavior::UnderNode(&parent_id))?;
+                self.nodes.insert(child, id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes `entity` and all of its children, the entities themselves are left alone.
+    pub fn remove(&mut self, entity: Entity) {
+        let id = match self.nodes.get(&entity) {
+            Some(id) => id.clone(),
+            None => return,
+        };
+        if let Ok(traversal) = self.tree.traverse_pre_order(&id) {
+            for node in traversal {
+                self.nodes.remove(node.data());
+            }
+        }
+        let _ = self.tree.remove_node(id, RemoveBehavior::DropChildren);
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.nodes.contains_key(&entity)
+    }
+
+    /// The direct children of `entity`, bottom most first.
+    pub fn children(&self, entity: Entity) -> Vec<Entity> {
+        self.nodes
+            .get(&entity)
+            .and_then(|id| self.tree.children(id).ok())
+            .map(|children| children.map(|node| *node.data()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Every element in the order they are drawn.
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.tree
+            .root_node_id()
+            .and_then(|id| self.tree.traverse_pre_order(id).ok())
+            .into_iter()
+            .flatten()
+            .map(|node| *node.data())
+    }
+
+    /// Draws every element on top of what is already on the surface. Elements missing
+    /// from `elements`, e.g. despawned entities, are skipped along with their children.
+    pub fn render(&self, surface: &mut Surface, elements: &RenderElements) {
+        if let Some(root) = self.tree.root_node_id() {
+            self.render_node(root, surface, elements);
+        }
+    }
+
+    fn render_node(&self, id: &NodeId, surface: &mut Surface, elements: &RenderElements) {
+        let entity = *self.tree.get(id).unwrap().data();
+        let (transform, clip, opacity, shape, text, image) = match elements.get(entity) {
+            Ok(element) => element,
+            Err(_) => return,
+        };
+
+        let canvas = surface.canvas();
+        let save_count = match opacity {
+            Some(Opacity(opacity)) if *opacity <= 0.0 => return,
+            Some(Opacity(opacity)) if *opacity < 1.0 => {
+                canvas.save_layer_alpha(None, (opacity * 255.0).round() as _)
+            }
+            _ => canvas.save(),
+        };
+        if let Some(transform) = transform {
+            transform.apply(canvas);
+        }
+        if let Some(clip) = clip {
+            clip.apply(canvas);
+        }
+
+        if let Some(DrawShape { shape, brush }) = shape {
+            match shape {
+                Shape::Rect(rect) => surface.draw_rect(*rect, brush),
+                Shape::RoundedRect {
+                    rect,
+                    radius_x,
+                    radius_y,
+                } => surface.draw_rounded_rect(*rect, *radius_x, *radius_y, brush),
+                Shape::Circle { center, radius } => surface.draw_circle(*center, *radius, brush),
+                Shape::Ellipse(bounds) => surface.draw_ellipse(*bounds, brush),
+                Shape::Line(from, to) => surface.draw_line(*from, *to, brush),
+                Shape::Polyline(points) => surface.draw_polyline(points, brush),
+                Shape::Path(path) => surface.draw_path(path, brush),
+            }
+        }
+        if let Some(DrawImage { image, dst, style }) = image {
+            surface.draw_image(image, *dst, style);
+        }
+        if let Some(DrawText { layout, position }) = text {
+            surface.draw_text(layout, *position);
+        }
+
+        for child in self.tree.children_ids(id).unwrap() {
+            self.render_node(child, surface, elements);
+        }
+        surface.canvas().restore_to_count(save_count);
+    }
+}
+
+impl Default for RenderElementTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
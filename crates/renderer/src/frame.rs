@@ -0,0 +1,107 @@
+use std::{fs, path::Path};
+
+use anyhow::Error;
+use skia::{image::CachingHint, AlphaType, ColorType, Data, EncodedImageFormat, ISize, ImageInfo};
+
+/// The contents of a surface as tightly packed, unpremultiplied RGBA8888 pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Increases by one for every frame presented to the owning [`FrameStore`](crate::FrameStore),
+    /// always 0 for captured frames.
+    pub index: u64,
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// Reads the top left `size` of `surface`, which is all of it for raster drivers.
+    pub(crate) fn read_from(surface: &mut skia::Surface, size: ISize) -> anyhow::Result<Self> {
+        let (width, height) = (size.width, size.height);
+        let row_bytes = width as usize * 4;
+        let mut pixels = vec![0; row_bytes * height as usize];
+        if !surface.read_pixels(
+            &Self::image_info(width, height),
+            &mut pixels,
+            row_bytes,
+            (0, 0),
+        ) {
+            return Err(Error::msg("Unable to read the Surface pixels"));
+        }
+        Ok(Self {
+            index: 0,
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+
+    /// Decodes a PNG (or any other format [`Image`](crate::image::Image) supports) into a frame.
+    pub fn from_png(bytes: &[u8]) -> anyhow::Result<Self> {
+        let image = match skia::Image::from_encoded(Data::new_copy(bytes)) {
+            Some(image) => image,
+            None => return Err(Error::msg("Unsupported or corrupt image data")),
+        };
+        let (width, height) = (image.width(), image.height());
+        let row_bytes = width as usize * 4;
+        let mut pixels = vec![0; row_bytes * height as usize];
+        if !image.read_pixels(
+            &Self::image_info(width, height),
+            &mut pixels,
+            row_bytes,
+            (0, 0),
+            CachingHint::Disallow,
+        ) {
+            return Err(Error::msg("Unable to read the Image pixels"));
+        }
+        Ok(Self {
+            index: 0,
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+
+    pub fn load_png(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_png(&bytes)
+    }
+
+    /// The RGBA value of the pixel at `(x, y)`, or `None` if it is out of bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y * self.width + x) as usize * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        Some(pixel)
+    }
+
+    pub fn encode_png(&self) -> anyhow::Result<Vec<u8>> {
+        let info = Self::image_info(self.width as i32, self.height as i32);
+        let image = skia::Image::from_raster_data(
+            &info,
+            Data::new_copy(&self.pixels),
+            self.width as usize * 4,
+        );
+        match image.and_then(|image| image.encode_to_data(EncodedImageFormat::PNG)) {
+            Some(data) => Ok(data.as_bytes().to_vec()),
+            None => Err(Error::msg("Unable to encode the Frame as PNG")),
+        }
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        fs::write(path, self.encode_png()?)?;
+        Ok(())
+    }
+
+    fn image_info(width: i32, height: i32) -> ImageInfo {
+        ImageInfo::new(
+            (width, height),
+            ColorType::RGBA8888,
+            AlphaType::Unpremul,
+            None,
+        )
+    }
+}
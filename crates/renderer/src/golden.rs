@@ -0,0 +1,239 @@
+//! Visual regression checks for [`Painter`](crate::Painter) output.
+//!
+//! Scenes are drawn with the CPU rasteriser into an offscreen [`Surface`] and compared
+//! against reference PNGs, so the checks run the same on machines without a GPU.
+//! Setting the `UPDATE_GOLDEN_IMAGES` environment variable writes the rendered scenes
+//! as the new references instead of comparing against them.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+
+use crate::{Frame, Surface};
+
+const UPDATE_ENV_VAR: &str = "UPDATE_GOLDEN_IMAGES";
+
+/// Compares rendered scenes against the reference PNGs in a directory.
+///
+/// References are stored as `<name>.png`. When a scene does not match, the rendered
+/// frame is written next to it as `<name>.actual.png` along with `<name>.diff.png`,
+/// which highlights the mismatched pixels in red over a faded copy of the reference.
+#[derive(Debug, Clone)]
+pub struct GoldenImages {
+    directory: PathBuf,
+    /// The largest difference allowed in any one channel before a pixel counts as changed.
+    pub tolerance: u8,
+    /// How many changed pixels are allowed before the check fails.
+    pub max_mismatched_pixels: usize,
+}
+
+impl GoldenImages {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            tolerance: 0,
+            max_mismatched_pixels: 0,
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_max_mismatched_pixels(mut self, max_mismatched_pixels: usize) -> Self {
+        self.max_mismatched_pixels = max_mismatched_pixels;
+        self
+    }
+
+    /// Draws a scene into a transparent `width` by `height` surface and reads it back.
+    pub fn render(
+        width: u32,
+        height: u32,
+        scene: impl FnOnce(&mut Surface),
+    ) -> anyhow::Result<Frame> {
+        let mut surface = Surface::new_offscreen(width, height)?;
+        scene(&mut surface);
+        surface.capture()
+    }
+
+    /// Renders a scene and compares it against the reference called `name`.
+    pub fn check(
+        &self,
+        name: &str,
+        width: u32,
+        height: u32,
+        scene: impl FnOnce(&mut Surface),
+    ) -> anyhow::Result<()> {
+        let frame = Self::render(width, height, scene)?;
+        self.compare(name, &frame)
+    }
+
+    /// Compares an already rendered frame against the reference called `name`.
+    pub fn compare(&self, name: &str, frame: &Frame) -> anyhow::Result<()> {
+        let reference_path = self.path_for(name, "png");
+        if env::var_os(UPDATE_ENV_VAR).is_some() {
+            fs::create_dir_all(&self.directory)?;
+            return frame.save_png(&reference_path);
+        }
+        if !reference_path.exists() {
+            self.save_actual(name, frame)?;
+            return Err(Error::msg(format!(
+                "No reference image at {}, run with {} set to create it",
+                reference_path.display(),
+                UPDATE_ENV_VAR
+            )));
+        }
+
+        let reference = Frame::load_png(&reference_path)?;
+        if (reference.width, reference.height) != (frame.width, frame.height) {
+            self.save_actual(name, frame)?;
+            return Err(Error::msg(format!(
+                "{} is {}x{} but the reference is {}x{}",
+                name, frame.width, frame.height, reference.width, reference.height
+            )));
+        }
+
+        let comparison = Comparison::new(&reference, frame, self.tolerance);
+        if comparison.mismatched_pixels <= self.max_mismatched_pixels {
+            return Ok(());
+        }
+        self.save_actual(name, frame)?;
+        comparison.diff.save_png(self.path_for(name, "diff.png"))?;
+        Err(Error::msg(format!(
+            "{} differs from its reference in {} pixels (up to {} per channel), see {}",
+            name,
+            comparison.mismatched_pixels,
+            comparison.max_difference,
+            self.path_for(name, "diff.png").display()
+        )))
+    }
+
+    fn save_actual(&self, name: &str, frame: &Frame) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        frame.save_png(self.path_for(name, "actual.png"))
+    }
+
+    fn path_for(&self, name: &str, extension: &str) -> PathBuf {
+        Path::new(&self.directory).join(format!("{}.{}", name, extension))
+    }
+}
+
+/// The per-pixel difference between two frames of the same size.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Pixels where any channel differs by more than the tolerance.
+    pub mismatched_pixels: usize,
+    /// The largest difference seen in any channel.
+    pub max_difference: u8,
+    pub diff: Frame,
+}
+
+impl Comparison {
+    /// Panics if the frames are not the same size.
+    pub fn new(expected: &Frame, actual: &Frame, tolerance: u8) -> Self {
+        assert_eq!(
+            (expected.width, expected.height),
+            (actual.width, actual.height),
+            "Compared frames must be the same size"
+        );
+
+        let mut mismatched_pixels = 0;
+        let mut max_difference = 0;
+        let mut diff = Vec::with_capacity(expected.pixels.len());
+        for (expected, actual) in expected
+            .pixels
+            .chunks_exact(4)
+            .zip(actual.pixels.chunks_exact(4))
+        {
+            let difference = expected
+                .iter()
+                .zip(actual)
+                .map(|(&e, &a)| e.abs_diff(a))
+                .max()
+                .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            if difference > tolerance {
+                mismatched_pixels += 1;
+                diff.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                let luma =
+                    (expected[0] as u32 * 3 + expected[1] as u32 * 6 + expected[2] as u32) / 10;
+                let faded = (luma / 4 + 192) as u8;
+                diff.extend_from_slice(&[faded, faded, faded, 255]);
+            }
+        }
+
+        Self {
+            mismatched_pixels,
+            max_difference,
+            diff: Frame {
+                index: 0,
+                width: expected.width,
+                height: expected.height,
+                pixels: diff,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, height: u32, pixels: &[[u8; 4]]) -> Frame {
+        Frame {
+            index: 0,
+            width,
+            height,
+            pixels: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn identical_frames_have_no_mismatches() {
+        let expected = frame(2, 1, &[[10, 20, 30, 255], [0, 0, 0, 0]]);
+        let comparison = Comparison::new(&expected, &expected.clone(), 0);
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_difference, 0);
+    }
+
+    #[test]
+    fn differences_within_tolerance_are_not_mismatches() {
+        let expected = frame(2, 1, &[[100, 100, 100, 255], [100, 100, 100, 255]]);
+        let actual = frame(2, 1, &[[103, 100, 100, 255], [100, 98, 100, 255]]);
+        let comparison = Comparison::new(&expected, &actual, 3);
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert_eq!(comparison.max_difference, 3);
+    }
+
+    #[test]
+    fn pixels_over_tolerance_are_counted_once() {
+        let expected = frame(3, 1, &[[0, 0, 0, 255], [0, 0, 0, 255], [0, 0, 0, 255]]);
+        // Every channel of the first pixel differs, it still only counts as one pixel.
+        let actual = frame(3, 1, &[[9, 9, 9, 250], [0, 0, 0, 255], [0, 0, 4, 255]]);
+        let comparison = Comparison::new(&expected, &actual, 3);
+        assert_eq!(comparison.mismatched_pixels, 2);
+        assert_eq!(comparison.max_difference, 9);
+    }
+
+    #[test]
+    fn diff_marks_mismatched_pixels_in_red() {
+        let expected = frame(2, 1, &[[0, 0, 0, 255], [0, 0, 0, 255]]);
+        let actual = frame(2, 1, &[[0, 0, 0, 255], [255, 255, 255, 255]]);
+        let comparison = Comparison::new(&expected, &actual, 0);
+        assert_eq!(comparison.diff.pixel(0, 0), Some([192, 192, 192, 255]));
+        assert_eq!(comparison.diff.pixel(1, 0), Some([255, 0, 0, 255]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Compared frames must be the same size")]
+    fn frames_of_different_sizes_panic() {
+        let expected = frame(1, 1, &[[0, 0, 0, 255]]);
+        let actual = frame(1, 2, &[[0, 0, 0, 255], [0, 0, 0, 255]]);
+        Comparison::new(&expected, &actual, 0);
+    }
+}
@@ -0,0 +1,109 @@
+use std::{fs, path::Path};
+
+use anyhow::Error;
+use skia::{CubicResampler, Data, FilterMode, MipmapMode, SamplingOptions};
+
+/// A decoded image that can be drawn with the [`Painter`](crate::Painter).
+///
+/// Cloning is cheap, the pixels are shared between clones.
+#[derive(Clone)]
+pub struct Image(pub(crate) skia::Image);
+
+impl Image {
+    /// Decodes a PNG, JPEG, WebP, GIF or BMP image.
+    pub fn from_encoded(bytes: &[u8]) -> anyhow::Result<Self> {
+        skia::Image::from_encoded(Data::new_copy(bytes)).map_or_else(
+            || Err(Error::msg("Unsupported or corrupt image data")),
+            |e| Ok(Self(e)),
+        )
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_encoded(&bytes)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.0.width() as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.0.height() as u32
+    }
+}
+
+impl std::fmt::Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .finish()
+    }
+}
+
+/// How an image is filtered when it is drawn at a different size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Sampling {
+    /// Blocky, the right choice for pixel art and 1:1 blits.
+    Nearest,
+    Linear,
+    /// Linear filtering between mipmap levels, best for heavy downscaling.
+    LinearMipmap,
+    /// Bicubic filtering, sharpest when upscaling.
+    Cubic,
+}
+
+impl From<Sampling> for SamplingOptions {
+    fn from(sampling: Sampling) -> Self {
+        match sampling {
+            Sampling::Nearest => SamplingOptions::new(FilterMode::Nearest, MipmapMode::None),
+            Sampling::Linear => SamplingOptions::new(FilterMode::Linear, MipmapMode::None),
+            Sampling::LinearMipmap => SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear),
+            Sampling::Cubic => CubicResampler::mitchell().into(),
+        }
+    }
+}
+
+impl From<Sampling> for FilterMode {
+    fn from(sampling: Sampling) -> Self {
+        match sampling {
+            Sampling::Nearest => FilterMode::Nearest,
+            _ => FilterMode::Linear,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImageStyle {
+    pub sampling: Sampling,
+    /// From 0.0 (invisible) to 1.0 (opaque).
+    pub opacity: f32,
+}
+
+impl ImageStyle {
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub(crate) fn to_paint(self) -> skia::Paint {
+        let mut paint = skia::Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_alpha_f(self.opacity.clamp(0.0, 1.0));
+        paint
+    }
+}
+
+impl Default for ImageStyle {
+    fn default() -> Self {
+        Self {
+            sampling: Sampling::Linear,
+            opacity: 1.0,
+        }
+    }
+}
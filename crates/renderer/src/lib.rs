@@ -1,17 +1,41 @@
 pub mod colors;
+mod damage;
 mod drivers;
+pub mod elements;
+mod frame;
+pub mod golden;
+pub mod image;
+pub mod painter;
+pub mod text;
 
-use bevy_ecs::prelude::{Component, Entity};
-use colors::Colour;
-use derive_deref::{Deref, DerefMut};
+use bevy_ecs::prelude::Component;
+pub use damage::Damage;
+pub use drivers::headless::FrameStore;
 use drivers::SkiaDriver;
+pub use drivers::{DriverKind, DriverPreference};
+pub use elements::RenderElementTree;
+pub use frame::Frame;
+pub use painter::Painter;
 use raw_window_handle::HasRawWindowHandle;
-use utilities::EcsPlugin;
-
-use id_tree::Tree;
+use std::ops::{Deref, DerefMut};
+use utilities::{tracing, EcsPlugin};
 
 pub trait WindowDrawTarget: HasRawWindowHandle + Send + Sync + 'static {
+    /// The size of the drawable area in physical pixels, the size surfaces are created with.
     fn get_draw_bounds(&self) -> (u32, u32);
+    /// How many physical pixels make up one logical unit, 2.0 on most HiDPI monitors.
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
+    /// The size of the drawable area in logical units, the units everything is drawn in.
+    fn get_logical_draw_bounds(&self) -> (f32, f32) {
+        let (width, height) = self.get_draw_bounds();
+        let scale_factor = self.scale_factor();
+        (
+            (width as f64 / scale_factor) as f32,
+            (height as f64 / scale_factor) as f32,
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -20,26 +44,246 @@ impl EcsPlugin for RenderingPlugin {
     fn build(_: &mut bevy_ecs::prelude::World, _: &mut bevy_ecs::schedule::Schedule) {}
 }
 
-pub trait Painter {
-    fn clear(&mut self, colour: impl Into<Colour>);
+/// Inserted as a resource, new windows are created without an OS window and their
+/// surfaces use the headless driver, recording every presented frame instead of drawing
+/// to the screen. Nothing needs a display.
+#[derive(Debug, Clone, Copy)]
+pub struct HeadlessRendering {
+    /// How many of the most recent frames each window keeps.
+    pub frame_capacity: usize,
+    /// The scale factor windows are created with, as if they were on a monitor with it.
+    pub scale_factor: f64,
 }
 
-#[derive(Component, Deref, DerefMut)]
-pub struct Surface(SkiaDriver);
+impl Default for HeadlessRendering {
+    fn default() -> Self {
+        Self {
+            frame_capacity: 1,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+/// What a window draws into, along with the [`Damage`] drawn since it was last presented
+/// and the scale factor of the window.
+///
+/// The driver works in physical pixels, while painting after [`Surface::begin_paint`] is
+/// done in logical units so it looks the same on every monitor.
+#[derive(Component)]
+pub struct Surface(SkiaDriver, Damage, f32);
+
+impl Deref for Surface {
+    type Target = SkiaDriver;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl DerefMut for Surface {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
 
 // TODO: Is this really safe to do?
 unsafe impl Send for Surface {}
 unsafe impl Sync for Surface {}
 
 impl Surface {
+    /// Creates a surface with the driver `preference` asks for, see [`Surface::driver_kind`]
+    /// for the one that was picked.
+    pub fn new(
+        target: &impl WindowDrawTarget,
+        preference: DriverPreference,
+    ) -> anyhow::Result<Self> {
+        let driver = SkiaDriver::new(target, preference)?;
+        tracing::info!("Created a surface with the {:?} driver", driver.kind());
+        Ok(Surface(driver, Damage::Full, target.scale_factor() as f32))
+    }
+
     #[inline(always)]
     pub fn new_cpu(target: &impl WindowDrawTarget) -> anyhow::Result<Self> {
-        Ok(Surface(SkiaDriver::new_cpu(target)?))
+        Ok(Surface(
+            SkiaDriver::new_cpu(target)?,
+            Damage::Full,
+            target.scale_factor() as f32,
+        ))
+    }
+
+    /// A surface drawn on the GPU through Vulkan.
+    #[inline(always)]
+    pub fn new_vulkan(target: &impl WindowDrawTarget) -> anyhow::Result<Self> {
+        Ok(Surface(
+            SkiaDriver::new_vulkan(target)?,
+            Damage::Full,
+            target.scale_factor() as f32,
+        ))
+    }
+
+    /// A surface drawn with the CPU rasteriser that is never shown, read it back with
+    /// [`Surface::capture`].
+    #[inline(always)]
+    pub fn new_offscreen(width: u32, height: u32) -> anyhow::Result<Self> {
+        Ok(Surface(
+            SkiaDriver::new_offscreen((width as i32, height as i32))?,
+            Damage::Full,
+            1.0,
+        ))
+    }
+
+    /// A surface that records every presented frame into `frames`. It needs no window, so
+    /// it works without a display, present it with [`Surface::present_headless`].
+    #[inline(always)]
+    pub fn new_headless(width: u32, height: u32, frames: FrameStore) -> anyhow::Result<Self> {
+        Ok(Surface(
+            SkiaDriver::new_headless((width as i32, height as i32), frames)?,
+            Damage::Full,
+            1.0,
+        ))
+    }
+
+    /// Records `damage`, in logical units, and clips drawing to everything damaged since
+    /// the last present. Drawing afterwards is scaled by the scale factor.
+    pub fn begin_paint(&mut self, damage: Damage) {
+        self.1.add(damage.scaled(self.2));
+        let size = self.0.size();
+        let area = self
+            .1
+            .to_pixels(size.width, size.height)
+            .unwrap_or_else(|| skia::IRect::from_size(size));
+        let canvas = self.0.canvas();
+        canvas.restore_to_count(1);
+        canvas.save();
+        // Clipped to whole pixels, the same pixels that are presented. The backing store
+        // can be larger than the surface, so this clips even when everything is damaged.
+        canvas.clip_rect(skia::Rect::from(area), skia::ClipOp::Intersect, false);
+        if self.2 != 1.0 {
+            canvas.scale((self.2, self.2));
+        }
+    }
+
+    /// Fits the surface to the current size of `target`, keeping the driver and what has
+    /// been drawn. Returns the newly exposed area in logical units, which is all that needs
+    /// painting, or [`Damage::Full`] when the scale factor changed. Content laid out
+    /// relative to the size of the window has to ask for a full repaint itself.
+    pub fn resize(&mut self, target: &impl WindowDrawTarget) -> anyhow::Result<Damage> {
+        let old_size = self.0.size();
+        self.0.resize_surface(target)?;
+        Ok(self.resized(old_size, target.scale_factor() as f32))
+    }
+
+    /// [`Surface::resize`] for headless surfaces, which have no window to take the size in
+    /// physical pixels and the scale factor from. Fails for surfaces that are not headless.
+    pub fn resize_headless(
+        &mut self,
+        width: u32,
+        height: u32,
+        scale_factor: f32,
+    ) -> anyhow::Result<Damage> {
+        let old_size = self.0.size();
+        self.0
+            .resize_headless(skia::ISize::new(width as i32, height as i32))?;
+        Ok(self.resized(old_size, scale_factor))
+    }
+
+    /// Picks up `scale_factor` and records what growing from `old_size` exposed.
+    fn resized(&mut self, old_size: skia::ISize, scale_factor: f32) -> Damage {
+        let size = self.0.size();
+        if scale_factor != self.2 {
+            self.2 = scale_factor;
+            self.1 = Damage::Full;
+            return Damage::Full;
+        }
+
+        // The old contents stay in the top left corner, growing exposes a strip to the
+        // right and one below them.
+        let mut exposed = Damage::Empty;
+        if size.width > old_size.width {
+            exposed.add_area(painter::Rect::new(
+                old_size.width as f32,
+                0.0,
+                (size.width - old_size.width) as f32,
+                size.height as f32,
+            ));
+        }
+        if size.height > old_size.height {
+            exposed.add_area(painter::Rect::new(
+                0.0,
+                old_size.height as f32,
+                size.width as f32,
+                (size.height - old_size.height) as f32,
+            ));
+        }
+        self.1.add(exposed);
+        exposed.scaled(1.0 / self.2)
+    }
+
+    /// How many physical pixels make up one logical unit.
+    pub fn scale_factor(&self) -> f32 {
+        self.2
+    }
+
+    /// Changes the scale used by [`Surface::begin_paint`], the whole surface has to be
+    /// repainted afterwards. Window surfaces follow the scale factor of their window.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.2 = scale_factor;
+        self.1 = Damage::Full;
+    }
+
+    /// The size of the surface in physical pixels.
+    pub fn size(&mut self) -> (u32, u32) {
+        let size = self.0.size();
+        (size.width as u32, size.height as u32)
+    }
+
+    /// The size of the surface in logical units.
+    pub fn logical_size(&mut self) -> (f32, f32) {
+        let (width, height) = self.size();
+        (width as f32 / self.2, height as f32 / self.2)
+    }
+
+    pub fn driver_kind(&self) -> DriverKind {
+        self.0.kind()
     }
 
+    /// Everything damaged since the last present.
+    pub fn damage(&self) -> Damage {
+        self.1
+    }
+
+    /// Presents the damaged part of the surface, all of it when nothing was recorded
+    /// with [`Surface::begin_paint`].
     #[inline(always)]
     pub fn present_surface(&mut self, target: &impl WindowDrawTarget) -> anyhow::Result<()> {
-        self.0.present_surface(target)
+        let size = self.0.size();
+        let area = self.1.to_pixels(size.width, size.height);
+        self.0.canvas().restore_to_count(1);
+        self.1 = Damage::Empty;
+        self.0.present_surface(target, area)
+    }
+
+    /// Records the surface into its [`FrameStore`] like presenting it to a window would.
+    /// Fails for surfaces that are not headless.
+    pub fn present_headless(&mut self) -> anyhow::Result<()> {
+        self.0.canvas().restore_to_count(1);
+        self.1 = Damage::Empty;
+        match &mut self.0 {
+            SkiaDriver::Headless(headless) => headless.record_frame(),
+            _ => Err(anyhow::Error::msg("Not a headless surface")),
+        }
+    }
+
+    /// Reads back what has been drawn to the surface so far.
+    pub fn capture(&mut self) -> anyhow::Result<Frame> {
+        let size = self.0.size();
+        Frame::read_from(self.0.surface_mut(), size)
+    }
+
+    /// The frames recorded by a headless surface, `None` for any other driver.
+    pub fn frames(&self) -> Option<&FrameStore> {
+        match &self.0 {
+            SkiaDriver::Headless(headless) => Some(&headless.1),
+            _ => None,
+        }
     }
 
     // ========================================================
@@ -48,6 +292,3 @@ impl Surface {
         panic!("Clone is not implimented for thread safety")
     }
 }
-
-#[derive(Component)]
-pub struct RenderElementTree(Tree<Entity>);
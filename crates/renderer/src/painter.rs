@@ -0,0 +1,466 @@
+use crate::{
+    colors::Colour,
+    image::{Image, ImageStyle},
+    text::TextLayout,
+    Surface,
+};
+
+pub trait Painter {
+    fn clear(&mut self, colour: impl Into<Colour>);
+    fn draw_rect(&mut self, rect: impl Into<Rect>, brush: &Brush);
+    fn draw_rounded_rect(
+        &mut self,
+        rect: impl Into<Rect>,
+        radius_x: f32,
+        radius_y: f32,
+        brush: &Brush,
+    );
+    fn draw_circle(&mut self, center: impl Into<Point>, radius: f32, brush: &Brush);
+    /// Draws the ellipse inscribed in `bounds`.
+    fn draw_ellipse(&mut self, bounds: impl Into<Rect>, brush: &Brush);
+    fn draw_line(&mut self, from: impl Into<Point>, to: impl Into<Point>, brush: &Brush);
+    /// Draws connected line segments through `points`, a fill brush fills the implicitly closed shape.
+    fn draw_polyline(&mut self, points: &[Point], brush: &Brush);
+    /// Draws part of the ellipse inscribed in `bounds`. Angles are in degrees, clockwise from
+    /// the positive x axis. With `use_center` the arc is closed through the center as a wedge.
+    fn draw_arc(
+        &mut self,
+        bounds: impl Into<Rect>,
+        start_angle: f32,
+        sweep_angle: f32,
+        use_center: bool,
+        brush: &Brush,
+    );
+    fn draw_path(&mut self, path: &Path, brush: &Brush);
+    /// Draws text shaped by a [`FontLibrary`](crate::text::FontLibrary), `position` is
+    /// the top left corner of the layout.
+    fn draw_text(&mut self, layout: &TextLayout, position: impl Into<Point>);
+    /// Draws the whole image scaled to fill `dst`.
+    fn draw_image(&mut self, image: &Image, dst: impl Into<Rect>, style: &ImageStyle);
+    /// Draws the `src` part of the image, in image pixels, scaled to fill `dst`.
+    fn draw_image_region(
+        &mut self,
+        image: &Image,
+        src: impl Into<Rect>,
+        dst: impl Into<Rect>,
+        style: &ImageStyle,
+    );
+    /// Nine-slice scaling, the corners outside `center` keep their size, the edges
+    /// stretch along one axis and `center` stretches along both to fill `dst`.
+    fn draw_image_nine(
+        &mut self,
+        image: &Image,
+        center: impl Into<Rect>,
+        dst: impl Into<Rect>,
+        style: &ImageStyle,
+    );
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<(f32, f32)> for Point {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Point> for skia::Point {
+    fn from(Point { x, y }: Point) -> Self {
+        skia::Point::new(x, y)
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl From<(f32, f32, f32, f32)> for Rect {
+    fn from((x, y, width, height): (f32, f32, f32, f32)) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl From<Rect> for skia::Rect {
+    fn from(rect: Rect) -> Self {
+        skia::Rect::from_xywh(rect.x, rect.y, rect.width, rect.height)
+    }
+}
+
+/// How shapes are painted, either filled or outlined with a [`Stroke`].
+#[derive(Debug, Clone)]
+pub struct Brush {
+    pub colour: Colour,
+    pub style: BrushStyle,
+    pub anti_alias: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum BrushStyle {
+    Fill,
+    Stroke(Stroke),
+}
+
+impl Brush {
+    pub fn fill(colour: impl Into<Colour>) -> Self {
+        Self {
+            colour: colour.into(),
+            style: BrushStyle::Fill,
+            anti_alias: true,
+        }
+    }
+
+    pub fn stroke(colour: impl Into<Colour>, stroke: Stroke) -> Self {
+        Self {
+            colour: colour.into(),
+            style: BrushStyle::Stroke(stroke),
+            anti_alias: true,
+        }
+    }
+
+    pub fn with_anti_alias(mut self, anti_alias: bool) -> Self {
+        self.anti_alias = anti_alias;
+        self
+    }
+
+    pub(crate) fn to_paint(&self) -> skia::Paint {
+        let mut paint = skia::Paint::default();
+        paint.set_anti_alias(self.anti_alias);
+        paint.set_color(self.colour);
+        match &self.style {
+            BrushStyle::Fill => {
+                paint.set_style(skia::PaintStyle::Fill);
+            }
+            BrushStyle::Stroke(stroke) => {
+                paint
+                    .set_style(skia::PaintStyle::Stroke)
+                    .set_stroke_width(stroke.width)
+                    .set_stroke_cap(stroke.cap.into())
+                    .set_stroke_join(stroke.join.into())
+                    .set_stroke_miter(stroke.miter_limit);
+                if let Some(dash) = &stroke.dash {
+                    paint.set_path_effect(skia::PathEffect::dash(&dash.intervals, dash.phase));
+                }
+            }
+        }
+        paint
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// Miter joins sharper than this ratio of the stroke width are drawn as bevels.
+    pub miter_limit: f32,
+    pub dash: Option<Dash>,
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    pub fn with_dash(mut self, dash: Dash) -> Self {
+        self.dash = Some(dash);
+        self
+    }
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dash: None,
+        }
+    }
+}
+
+/// Alternating on and off lengths, `phase` offsets where the pattern starts.
+/// An odd number of intervals is repeated to make the pattern even.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dash {
+    pub intervals: Vec<f32>,
+    pub phase: f32,
+}
+
+impl Dash {
+    pub fn new(intervals: impl Into<Vec<f32>>, phase: f32) -> Self {
+        let mut intervals = intervals.into();
+        if intervals.len() % 2 == 1 {
+            intervals.extend_from_within(..);
+        }
+        Self { intervals, phase }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl From<LineCap> for skia::PaintCap {
+    fn from(cap: LineCap) -> Self {
+        match cap {
+            LineCap::Butt => skia::PaintCap::Butt,
+            LineCap::Round => skia::PaintCap::Round,
+            LineCap::Square => skia::PaintCap::Square,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl From<LineJoin> for skia::PaintJoin {
+    fn from(join: LineJoin) -> Self {
+        match join {
+            LineJoin::Miter => skia::PaintJoin::Miter,
+            LineJoin::Round => skia::PaintJoin::Round,
+            LineJoin::Bevel => skia::PaintJoin::Bevel,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// An arbitrary shape built from lines and curves.
+#[derive(Debug, Clone, Default)]
+pub struct Path(pub(crate) skia::Path);
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, point: impl Into<Point>) -> &mut Self {
+        self.0.move_to(point.into());
+        self
+    }
+
+    pub fn line_to(&mut self, point: impl Into<Point>) -> &mut Self {
+        self.0.line_to(point.into());
+        self
+    }
+
+    pub fn quad_to(&mut self, control: impl Into<Point>, end: impl Into<Point>) -> &mut Self {
+        self.0.quad_to(control.into(), end.into());
+        self
+    }
+
+    pub fn cubic_to(
+        &mut self,
+        control_1: impl Into<Point>,
+        control_2: impl Into<Point>,
+        end: impl Into<Point>,
+    ) -> &mut Self {
+        self.0
+            .cubic_to(control_1.into(), control_2.into(), end.into());
+        self
+    }
+
+    /// Appends part of the ellipse inscribed in `bounds`, connected to the current point by a line.
+    pub fn arc_to(
+        &mut self,
+        bounds: impl Into<Rect>,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> &mut Self {
+        self.0.arc_to(
+            skia::Rect::from(bounds.into()),
+            start_angle,
+            sweep_angle,
+            false,
+        );
+        self
+    }
+
+    pub fn close(&mut self) -> &mut Self {
+        self.0.close();
+        self
+    }
+
+    pub fn set_fill_rule(&mut self, rule: FillRule) -> &mut Self {
+        self.0.set_fill_type(match rule {
+            FillRule::NonZero => skia::PathFillType::Winding,
+            FillRule::EvenOdd => skia::PathFillType::EvenOdd,
+        });
+        self
+    }
+}
+
+impl Painter for Surface {
+    fn clear(&mut self, colour: impl Into<Colour>) {
+        self.canvas().clear(skia::Color::from(colour.into()));
+    }
+
+    fn draw_rect(&mut self, rect: impl Into<Rect>, brush: &Brush) {
+        self.canvas()
+            .draw_rect(skia::Rect::from(rect.into()), &brush.to_paint());
+    }
+
+    fn draw_rounded_rect(
+        &mut self,
+        rect: impl Into<Rect>,
+        radius_x: f32,
+        radius_y: f32,
+        brush: &Brush,
+    ) {
+        let rrect = skia::RRect::new_rect_xy(skia::Rect::from(rect.into()), radius_x, radius_y);
+        self.canvas().draw_rrect(rrect, &brush.to_paint());
+    }
+
+    fn draw_circle(&mut self, center: impl Into<Point>, radius: f32, brush: &Brush) {
+        self.canvas()
+            .draw_circle(center.into(), radius, &brush.to_paint());
+    }
+
+    fn draw_ellipse(&mut self, bounds: impl Into<Rect>, brush: &Brush) {
+        self.canvas()
+            .draw_oval(skia::Rect::from(bounds.into()), &brush.to_paint());
+    }
+
+    fn draw_line(&mut self, from: impl Into<Point>, to: impl Into<Point>, brush: &Brush) {
+        self.canvas()
+            .draw_line(from.into(), to.into(), &brush.to_paint());
+    }
+
+    fn draw_polyline(&mut self, points: &[Point], brush: &Brush) {
+        let points = points
+            .iter()
+            .map(|&point| skia::Point::from(point))
+            .collect::<Vec<_>>();
+        let path = skia::Path::polygon(&points, false, None, None);
+        self.canvas().draw_path(&path, &brush.to_paint());
+    }
+
+    fn draw_arc(
+        &mut self,
+        bounds: impl Into<Rect>,
+        start_angle: f32,
+        sweep_angle: f32,
+        use_center: bool,
+        brush: &Brush,
+    ) {
+        self.canvas().draw_arc(
+            skia::Rect::from(bounds.into()),
+            start_angle,
+            sweep_angle,
+            use_center,
+            &brush.to_paint(),
+        );
+    }
+
+    fn draw_path(&mut self, path: &Path, brush: &Brush) {
+        self.canvas().draw_path(&path.0, &brush.to_paint());
+    }
+
+    fn draw_text(&mut self, layout: &TextLayout, position: impl Into<Point>) {
+        layout.0.paint(self.canvas(), position.into());
+    }
+
+    fn draw_image(&mut self, image: &Image, dst: impl Into<Rect>, style: &ImageStyle) {
+        self.canvas().draw_image_rect_with_sampling_options(
+            &image.0,
+            None,
+            skia::Rect::from(dst.into()),
+            style.sampling,
+            &style.to_paint(),
+        );
+    }
+
+    fn draw_image_region(
+        &mut self,
+        image: &Image,
+        src: impl Into<Rect>,
+        dst: impl Into<Rect>,
+        style: &ImageStyle,
+    ) {
+        let src = skia::Rect::from(src.into());
+        self.canvas().draw_image_rect_with_sampling_options(
+            &image.0,
+            Some((&src, skia::canvas::SrcRectConstraint::Strict)),
+            skia::Rect::from(dst.into()),
+            style.sampling,
+            &style.to_paint(),
+        );
+    }
+
+    fn draw_image_nine(
+        &mut self,
+        image: &Image,
+        center: impl Into<Rect>,
+        dst: impl Into<Rect>,
+        style: &ImageStyle,
+    ) {
+        let center = skia::Rect::from(center.into()).round();
+        self.canvas().draw_image_nine(
+            &image.0,
+            center,
+            skia::Rect::from(dst.into()),
+            style.sampling.into(),
+            Some(&style.to_paint()),
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::Error;
+use skia::{
+    font_style::{Slant, Weight, Width},
+    textlayout::{self, FontCollection, ParagraphBuilder, ParagraphStyle, TypefaceFontProvider},
+    FontMgr, FontStyle,
+};
+
+use crate::colors::Colour;
+
+/// The fonts text is shaped with.
+///
+/// Installed system fonts can be used by family name straight away, fonts loaded
+/// from files are registered on top of them and win when both share a family name.
+/// Shaping goes through HarfBuzz, so ligatures, kerning and bidirectional text are
+/// handled for every font.
+///
+/// The library is neither `Send` nor `Sync`: its font collection is shared with every
+/// [`TextLayout`] it creates and skia's caches aren't synchronised, so it has to stay
+/// on the thread that created it.
+pub struct FontLibrary {
+    collection: FontCollection,
+    provider: TypefaceFontProvider,
+    font_mgr: FontMgr,
+}
+
+impl FontLibrary {
+    pub fn new() -> Self {
+        let font_mgr = FontMgr::new();
+        let provider = TypefaceFontProvider::new();
+        let mut collection = FontCollection::new();
+        collection.set_asset_font_manager(Some(provider.clone().into()));
+        collection.set_default_font_manager(font_mgr.clone(), None);
+        Self {
+            collection,
+            provider,
+            font_mgr,
+        }
+    }
+
+    /// Registers a TrueType/OpenType font and returns its family name.
+    pub fn load_font_data(&mut self, data: &[u8]) -> anyhow::Result<String> {
+        let typeface = match self.font_mgr.new_from_data(data, None) {
+            Some(typeface) => typeface,
+            None => return Err(Error::msg("Unable to decode font data")),
+        };
+        let family_name = typeface.family_name();
+        self.provider.register_typeface(typeface, None::<&str>);
+        // Layouts cached before this font existed may have resolved to a fallback.
+        self.collection.clear_caches();
+        Ok(family_name)
+    }
+
+    pub fn load_font_file(&mut self, path: impl AsRef<Path>) -> anyhow::Result<String> {
+        let data = fs::read(path)?;
+        self.load_font_data(&data)
+    }
+
+    /// Registers every `.ttf`, `.otf` and `.ttc` file under `directory`, including
+    /// sub directories. Returns the family names that were loaded.
+    ///
+    /// Files that fail to decode and sub directories that can't be read are skipped
+    /// rather than failing the whole directory. Symlinked directories are followed,
+    /// but each directory is only scanned once so symlink loops terminate.
+    pub fn load_font_directory(
+        &mut self,
+        directory: impl AsRef<Path>,
+    ) -> anyhow::Result<Vec<String>> {
+        let directory = fs::canonicalize(directory)?;
+        let mut family_names = Vec::new();
+        let mut directories = vec![fs::read_dir(&directory)?];
+        let mut visited = HashSet::from([directory]);
+        while let Some(entries) = directories.pop() {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    let is_new = fs::canonicalize(&path)
+                        .map(|directory| visited.insert(directory))
+                        .unwrap_or(false);
+                    if is_new {
+                        if let Ok(entries) = fs::read_dir(&path) {
+                            directories.push(entries);
+                        }
+                    }
+                    continue;
+                }
+                let is_font = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .map(|extension| {
+                        matches!(
+                            extension.to_ascii_lowercase().as_str(),
+                            "ttf" | "otf" | "ttc"
+                        )
+                    })
+                    .unwrap_or(false);
+                if !is_font {
+                    continue;
+                }
+                if let Ok(family_name) = self.load_font_file(&path) {
+                    if !family_names.contains(&family_name) {
+                        family_names.push(family_name);
+                    }
+                }
+            }
+        }
+        Ok(family_names)
+    }
+
+    /// Shapes `text` and breaks it into lines no wider than `max_width`.
+    pub fn layout(&mut self, text: &str, style: &TextStyle, max_width: f32) -> TextLayout {
+        let mut paragraph_style = ParagraphStyle::new();
+        paragraph_style
+            .set_text_align(style.align.into())
+            .set_text_direction(style.direction.into());
+        if let Some(max_lines) = style.max_lines {
+            paragraph_style
+                .set_max_lines(max_lines)
+                .set_ellipsis("\u{2026}");
+        }
+
+        let slant = if style.italic {
+            Slant::Italic
+        } else {
+            Slant::Upright
+        };
+        let mut text_style = textlayout::TextStyle::new();
+        text_style
+            .set_color(style.colour)
+            .set_font_size(style.size)
+            .set_font_style(FontStyle::new(
+                Weight::from(style.weight.0 as i32),
+                Width::NORMAL,
+                slant,
+            ));
+        if !style.font_families.is_empty() {
+            text_style.set_font_families(&style.font_families);
+        }
+        if let Some(line_height) = style.line_height {
+            text_style.set_height(line_height).set_height_override(true);
+        }
+        paragraph_style.set_text_style(&text_style);
+
+        let mut builder = ParagraphBuilder::new(&paragraph_style, self.collection.clone());
+        builder.push_style(&text_style).add_text(text);
+        let mut paragraph = builder.build();
+        paragraph.layout(max_width);
+        TextLayout(paragraph)
+    }
+
+    /// The size `text` takes up when laid out with [`FontLibrary::layout`].
+    pub fn measure(&mut self, text: &str, style: &TextStyle, max_width: f32) -> (f32, f32) {
+        let layout = self.layout(text, style, max_width);
+        (layout.width(), layout.height())
+    }
+}
+
+impl Default for FontLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextStyle {
+    /// Tried in order, falling back to the platform default when none match.
+    pub font_families: Vec<String>,
+    pub size: f32,
+    pub weight: FontWeight,
+    pub italic: bool,
+    pub colour: Colour,
+    pub align: TextAlign,
+    /// The paragraph's base direction, runs in the other direction are still reordered.
+    pub direction: TextDirection,
+    /// A multiple of the font size, `None` uses the font's own line spacing.
+    pub line_height: Option<f32>,
+    /// Text past this many lines is cut off with an ellipsis.
+    pub max_lines: Option<usize>,
+}
+
+impl TextStyle {
+    pub fn new(size: f32, colour: impl Into<Colour>) -> Self {
+        Self {
+            size,
+            colour: colour.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_font_family(mut self, family: impl Into<String>) -> Self {
+        self.font_families.push(family.into());
+        self
+    }
+
+    pub fn with_weight(mut self, weight: FontWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: TextDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = Some(line_height);
+        self
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_families: Vec::new(),
+            size: 14.0,
+            weight: FontWeight::NORMAL,
+            italic: false,
+            colour: Colour::BLACK.into(),
+            align: TextAlign::Start,
+            direction: TextDirection::LeftToRight,
+            line_height: None,
+            max_lines: None,
+        }
+    }
+}
+
+/// A CSS style font weight between 1 and 1000.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FontWeight(pub u16);
+
+impl FontWeight {
+    pub const THIN: FontWeight = FontWeight(100);
+    pub const EXTRA_LIGHT: FontWeight = FontWeight(200);
+    pub const LIGHT: FontWeight = FontWeight(300);
+    pub const NORMAL: FontWeight = FontWeight(400);
+    pub const MEDIUM: FontWeight = FontWeight(500);
+    pub const SEMI_BOLD: FontWeight = FontWeight(600);
+    pub const BOLD: FontWeight = FontWeight(700);
+    pub const EXTRA_BOLD: FontWeight = FontWeight(800);
+    pub const BLACK: FontWeight = FontWeight(900);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Right,
+    Center,
+    Justify,
+    /// Left for left-to-right text, right for right-to-left text.
+    Start,
+    End,
+}
+
+impl From<TextAlign> for textlayout::TextAlign {
+    fn from(align: TextAlign) -> Self {
+        match align {
+            TextAlign::Left => textlayout::TextAlign::Left,
+            TextAlign::Right => textlayout::TextAlign::Right,
+            TextAlign::Center => textlayout::TextAlign::Center,
+            TextAlign::Justify => textlayout::TextAlign::Justify,
+            TextAlign::Start => textlayout::TextAlign::Start,
+            TextAlign::End => textlayout::TextAlign::End,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+impl From<TextDirection> for textlayout::TextDirection {
+    fn from(direction: TextDirection) -> Self {
+        match direction {
+            TextDirection::LeftToRight => textlayout::TextDirection::LTR,
+            TextDirection::RightToLeft => textlayout::TextDirection::RTL,
+        }
+    }
+}
+
+/// Shaped text ready to be measured or drawn with [`Painter::draw_text`](crate::Painter::draw_text).
+pub struct TextLayout(pub(crate) textlayout::Paragraph);
+
+impl TextLayout {
+    /// The width of the widest line.
+    pub fn width(&self) -> f32 {
+        self.0.longest_line()
+    }
+
+    pub fn height(&self) -> f32 {
+        self.0.height()
+    }
+
+    /// The width the text would need to fit on as few lines as possible.
+    pub fn max_intrinsic_width(&self) -> f32 {
+        self.0.max_intrinsic_width()
+    }
+
+    /// The distance from the top of the layout to the first line's baseline.
+    pub fn baseline(&self) -> f32 {
+        self.0.alphabetic_baseline()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.0.line_number()
+    }
+
+    /// Lays the text out again for a new maximum width without reshaping it.
+    pub fn relayout(&mut self, max_width: f32) {
+        self.0.layout(max_width);
+    }
+}
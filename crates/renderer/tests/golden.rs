@@ -0,0 +1,55 @@
+//! Renders a scene through the headless driver, without a window, and compares the
+//! presented frame against `tests/golden/headless_rects.png`.
+
+use renderer::{
+    golden::GoldenImages,
+    painter::{Brush, Painter},
+    Damage, FrameStore, Surface,
+};
+
+fn references() -> GoldenImages {
+    GoldenImages::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+}
+
+#[test]
+fn headless_frame_matches_reference() -> anyhow::Result<()> {
+    let frames = FrameStore::with_capacity(1);
+    let mut surface = Surface::new_headless(32, 24, frames.clone())?;
+
+    surface.begin_paint(Damage::Full);
+    surface.clear((255, 255, 255));
+    // Pixel aligned and without anti aliasing, so every rasteriser produces the same pixels.
+    surface.draw_rect(
+        (4.0, 4.0, 16.0, 8.0),
+        &Brush::fill((200, 40, 40)).with_anti_alias(false),
+    );
+    surface.draw_rect(
+        (12.0, 10.0, 12.0, 10.0),
+        &Brush::fill((40, 80, 200)).with_anti_alias(false),
+    );
+    surface.present_headless()?;
+
+    assert_eq!(frames.presented_count(), 1);
+    let frame = frames.latest().expect("A frame was presented");
+    references().compare("headless_rects", &frame)
+}
+
+#[test]
+fn headless_frame_differing_from_reference_fails() -> anyhow::Result<()> {
+    let frames = FrameStore::with_capacity(1);
+    let mut surface = Surface::new_headless(32, 24, frames.clone())?;
+
+    surface.begin_paint(Damage::Full);
+    surface.clear((255, 255, 255));
+    surface.present_headless()?;
+
+    let frame = frames.latest().expect("A frame was presented");
+    let reference = renderer::Frame::load_png(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/golden/headless_rects.png"
+    ))?;
+    let comparison = renderer::golden::Comparison::new(&reference, &frame, 0);
+    // The red rectangle and the blue one, less the 8x2 pixels where they overlap.
+    assert_eq!(comparison.mismatched_pixels, 16 * 8 + 12 * 10 - 8 * 2);
+    Ok(())
+}
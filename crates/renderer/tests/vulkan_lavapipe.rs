@@ -0,0 +1,116 @@
+//! Creates the Vulkan driver for an X11 window, renders into it and reads the frame back.
+//!
+//! Ignored by default since it needs an X server and a Vulkan implementation. It is meant
+//! to run on lavapipe, the Mesa software rasteriser, so it works on CI machines without
+//! a GPU:
+//!
+//! ```text
+//! VK_ICD_FILENAMES=/usr/share/vulkan/icd.d/lvp_icd.x86_64.json \
+//!     xvfb-run cargo test -p renderer --test vulkan_lavapipe -- --ignored
+//! ```
+#![cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+
+use std::ptr;
+
+use anyhow::Error;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle, XlibHandle};
+use renderer::{
+    painter::{Brush, Painter},
+    Damage, DriverKind, Surface, WindowDrawTarget,
+};
+use x11_dl::xlib::{self, Xlib};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+
+/// A mapped Xlib window, just enough to create a swapchain for.
+struct TestWindow {
+    xlib: Xlib,
+    // Stored as an address so the window can be a `WindowDrawTarget`, which has to be
+    // `Send + Sync`. The test only uses it from one thread.
+    display: usize,
+    window: xlib::Window,
+}
+
+impl TestWindow {
+    fn open(width: u32, height: u32) -> anyhow::Result<Self> {
+        let xlib = Xlib::open()?;
+        let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+        if display.is_null() {
+            return Err(Error::msg("Unable to open the X display, is DISPLAY set?"));
+        }
+        let window = unsafe {
+            let screen = (xlib.XDefaultScreen)(display);
+            let root = (xlib.XRootWindow)(display, screen);
+            let window = (xlib.XCreateSimpleWindow)(display, root, 0, 0, width, height, 0, 0, 0);
+            (xlib.XMapWindow)(display, window);
+            (xlib.XSync)(display, xlib::False);
+            window
+        };
+        Ok(Self {
+            xlib,
+            display: display as usize,
+            window,
+        })
+    }
+}
+
+impl Drop for TestWindow {
+    fn drop(&mut self) {
+        let display = self.display as *mut xlib::Display;
+        unsafe {
+            (self.xlib.XDestroyWindow)(display, self.window);
+            (self.xlib.XCloseDisplay)(display);
+        }
+    }
+}
+
+unsafe impl HasRawWindowHandle for TestWindow {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        let mut handle = XlibHandle::empty();
+        handle.window = self.window;
+        handle.display = self.display as *mut _;
+        RawWindowHandle::Xlib(handle)
+    }
+}
+
+impl WindowDrawTarget for TestWindow {
+    fn get_draw_bounds(&self) -> (u32, u32) {
+        (WIDTH, HEIGHT)
+    }
+}
+
+#[test]
+#[ignore = "needs an X server and a Vulkan implementation such as lavapipe"]
+fn vulkan_renders_and_reads_back_a_frame() -> anyhow::Result<()> {
+    let window = TestWindow::open(WIDTH, HEIGHT)?;
+    let mut surface = Surface::new_vulkan(&window)?;
+    assert_eq!(surface.driver_kind(), DriverKind::Vulkan);
+    assert_eq!(surface.size(), (WIDTH, HEIGHT));
+
+    surface.begin_paint(Damage::Full);
+    surface.clear((255, 255, 255));
+    surface.draw_rect(
+        (8.0, 8.0, 16.0, 16.0),
+        &Brush::fill((200, 40, 40)).with_anti_alias(false),
+    );
+    surface.present_surface(&window)?;
+
+    let frame = surface.capture()?;
+    assert_eq!((frame.width, frame.height), (WIDTH, HEIGHT));
+    assert_eq!(frame.pixel(0, 0), Some([255, 255, 255, 255]));
+    assert_eq!(frame.pixel(8, 8), Some([200, 40, 40, 255]));
+    assert_eq!(frame.pixel(23, 23), Some([200, 40, 40, 255]));
+    assert_eq!(frame.pixel(24, 24), Some([255, 255, 255, 255]));
+    assert_eq!(
+        frame.pixel(WIDTH - 1, HEIGHT - 1),
+        Some([255, 255, 255, 255])
+    );
+    Ok(())
+}
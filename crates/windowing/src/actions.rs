@@ -0,0 +1,605 @@
+//! Named actions bound to key and mouse chords, sent as typed events.
+//!
+//! An application lists its actions in an enum implementing [`Action`], adds an
+//! [`ActionPlugin`] for it and binds chords in the [`ActionBindings`] resource. Bindings
+//! are global, or only apply to windows given a matching scope with
+//! [`WindowContext::set_input_scope`](crate::window::WindowContext::set_input_scope).
+//!
+//! Bindings can be loaded from and saved to a file, one `action = chord` per line with
+//! scoped bindings under a `[scope]` heading:
+//!
+//! ```text
+//! # Global bindings
+//! save = Ctrl+Shift+S
+//!
+//! [editor]
+//! select = Ctrl+MouseLeft
+//! ```
+
+use std::{fmt, fs, marker::PhantomData, path::Path, str::FromStr};
+
+use anyhow::Error;
+use bevy_ecs::{
+    event::Events,
+    prelude::{Component, Entity, EventReader, EventWriter, Query, Res, World},
+    schedule::{Schedule, SystemStage},
+    system::assert_is_system,
+};
+use utilities::{tracing, CoreStages, EcsPlugin, Event};
+
+use crate::input::{KeyCode, KeyboardInput, ModifiersState, MouseButton, MouseInput};
+
+/// Something a chord can trigger, usually a fieldless enum.
+pub trait Action: Copy + Eq + fmt::Debug + Send + Sync + 'static {
+    /// The name of the action in binding files.
+    fn name(&self) -> &'static str;
+    fn from_name(name: &str) -> Option<Self>;
+}
+
+/// Sent in `CoreStages::PreUpdate` when a chord bound to `action` is pressed on `entity`.
+#[derive(Debug, Copy, Clone)]
+pub struct ActionTriggered<A: Action> {
+    pub entity: Entity,
+    pub action: A,
+}
+
+impl<A: Action> Event for ActionTriggered<A> {}
+
+/// The key or mouse button that completes a [`Chord`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChordInput {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// A key or mouse button pressed while holding exactly `modifiers`, written like
+/// `Ctrl+Shift+S` or `Alt+MouseLeft`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: ModifiersState,
+    pub input: ChordInput,
+}
+
+impl Chord {
+    pub fn key(modifiers: ModifiersState, key: KeyCode) -> Self {
+        Self {
+            modifiers,
+            input: ChordInput::Key(key),
+        }
+    }
+
+    pub fn mouse(modifiers: ModifiersState, button: MouseButton) -> Self {
+        Self {
+            modifiers,
+            input: ChordInput::Mouse(button),
+        }
+    }
+
+    /// Whether pressing `input` while holding `modifiers` completes the chord. Modifiers
+    /// other than Ctrl, Alt, Shift and Super are ignored.
+    pub fn matches(&self, modifiers: ModifiersState, input: ChordInput) -> bool {
+        let chord_modifiers = ModifiersState::CONTROL
+            | ModifiersState::ALT
+            | ModifiersState::SHIFT
+            | ModifiersState::SUPER;
+        self.input == input && self.modifiers == modifiers & chord_modifiers
+    }
+}
+
+impl FromStr for Chord {
+    type Err = Error;
+
+    fn from_str(chord: &str) -> anyhow::Result<Self> {
+        let mut modifiers = ModifiersState::empty();
+        let mut input = None;
+        for token in chord.split('+').map(str::trim) {
+            if input.is_some() {
+                return Err(Error::msg(format!(
+                    "\"{}\" has to end with its only key or mouse button",
+                    chord
+                )));
+            }
+            match token.to_uppercase().as_str() {
+                "" => return Err(Error::msg(format!("\"{}\" has an empty part", chord))),
+                "CTRL" | "CONTROL" => modifiers.insert(ModifiersState::CONTROL),
+                "ALT" | "OPTION" => modifiers.insert(ModifiersState::ALT),
+                "SHIFT" => modifiers.insert(ModifiersState::SHIFT),
+                "SUPER" | "CMD" | "COMMAND" => modifiers.insert(ModifiersState::SUPER),
+                // The platform's usual shortcut modifier.
+                "CMDORCTRL" if cfg!(target_os = "macos") => modifiers.insert(ModifiersState::SUPER),
+                "CMDORCTRL" => modifiers.insert(ModifiersState::CONTROL),
+                "MOUSELEFT" => input = Some(ChordInput::Mouse(MouseButton::Left)),
+                "MOUSERIGHT" => input = Some(ChordInput::Mouse(MouseButton::Right)),
+                "MOUSEMIDDLE" => input = Some(ChordInput::Mouse(MouseButton::Middle)),
+                other => match other.strip_prefix("MOUSE").map(u16::from_str) {
+                    Some(Ok(button)) => input = Some(ChordInput::Mouse(MouseButton::Other(button))),
+                    _ => input = Some(ChordInput::Key(parse_key(token)?)),
+                },
+            }
+        }
+        match input {
+            Some(input) => Ok(Self { modifiers, input }),
+            None => Err(Error::msg(format!(
+                "\"{}\" has no key or mouse button",
+                chord
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (ModifiersState::CONTROL, "Ctrl+"),
+            (ModifiersState::ALT, "Alt+"),
+            (ModifiersState::SHIFT, "Shift+"),
+            (ModifiersState::SUPER, "Super+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.input {
+            ChordInput::Key(key) => f.write_str(&key_name(key)),
+            ChordInput::Mouse(MouseButton::Left) => f.write_str("MouseLeft"),
+            ChordInput::Mouse(MouseButton::Right) => f.write_str("MouseRight"),
+            ChordInput::Mouse(MouseButton::Middle) => f.write_str("MouseMiddle"),
+            ChordInput::Mouse(MouseButton::Other(button)) => write!(f, "Mouse{}", button),
+            ChordInput::Mouse(button) => write!(f, "{:?}", button),
+        }
+    }
+}
+
+fn parse_key(name: &str) -> anyhow::Result<KeyCode> {
+    match KeyCode::from_str(name) {
+        Ok(KeyCode::Unidentified(_)) | Err(_) => {
+            Err(Error::msg(format!("\"{}\" is not a known key", name)))
+        }
+        Ok(key) => Ok(key),
+    }
+}
+
+/// The name tao parses `key` from, e.g. `S` for `KeyCode::KeyS`.
+fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    ["Key", "Digit", "Arrow"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .filter(|stripped| !stripped.is_empty())
+        .map_or_else(|| name.clone(), str::to_string)
+}
+
+/// One chord and the action it triggers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding<A: Action> {
+    pub action: A,
+    pub chord: Chord,
+    /// Only windows with this input scope trigger the binding, `None` for every window.
+    pub scope: Option<String>,
+}
+
+/// The chords bound to every action of type `A`, inserted as a resource by
+/// [`ActionPlugin`]. Changes apply from the next frame.
+///
+/// A chord bound in a window's scope hides the same chord bound globally.
+#[derive(Debug, Clone)]
+pub struct ActionBindings<A: Action> {
+    bindings: Vec<Binding<A>>,
+}
+
+impl<A: Action> ActionBindings<A> {
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Binds `chord` to `action` for every window, actions can have any number of chords.
+    pub fn bind(&mut self, action: A, chord: Chord) {
+        self.bind_scoped(action, chord, None::<String>);
+    }
+
+    /// Binds `chord` to `action` for windows with the input scope `scope`, or every
+    /// window when `None`. A chord triggers one action per scope, binding it again
+    /// replaces the old action.
+    pub fn bind_scoped(&mut self, action: A, chord: Chord, scope: Option<impl Into<String>>) {
+        let scope = scope.map(Into::into);
+        self.bindings
+            .retain(|binding| binding.chord != chord || binding.scope != scope);
+        self.bindings.push(Binding {
+            action,
+            chord,
+            scope,
+        });
+    }
+
+    /// Replaces every chord of `action` in `scope` with `chord`.
+    pub fn rebind(&mut self, action: A, chord: Chord, scope: Option<impl Into<String>>) {
+        let scope = scope.map(Into::into);
+        self.bindings
+            .retain(|binding| binding.action != action || binding.scope != scope);
+        self.bind_scoped(action, chord, scope);
+    }
+
+    /// Removes every chord of `action`, in all scopes.
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.retain(|binding| binding.action != action);
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    pub fn bindings(&self) -> &[Binding<A>] {
+        &self.bindings
+    }
+
+    /// The chords bound to `action` in any scope.
+    pub fn chords(&self, action: A) -> impl Iterator<Item = &Chord> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.action == action)
+            .map(|binding| &binding.chord)
+    }
+
+    /// The actions pressing `input` with `modifiers` triggers in a window with `scope`.
+    pub fn triggered<'a>(
+        &'a self,
+        modifiers: ModifiersState,
+        input: ChordInput,
+        scope: Option<&'a str>,
+    ) -> impl Iterator<Item = A> + 'a {
+        let matching = move |binding: &&Binding<A>| binding.chord.matches(modifiers, input);
+        let scoped = self
+            .bindings
+            .iter()
+            .filter(matching)
+            .any(|binding| scope.is_some() && binding.scope.as_deref() == scope);
+        self.bindings
+            .iter()
+            .filter(matching)
+            .filter(move |binding| match binding.scope.as_deref() {
+                Some(binding_scope) => Some(binding_scope) == scope,
+                None => !scoped,
+            })
+            .map(|binding| binding.action)
+    }
+
+    /// Parses bindings in the format described in the [module docs](self).
+    pub fn from_config(config: &str) -> anyhow::Result<Self> {
+        let mut bindings = Self::new();
+        let mut scope = None;
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(heading) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                scope = Some(heading.trim().to_string());
+                continue;
+            }
+            let (name, chord) = line.split_once('=').map_or_else(
+                || {
+                    Err(Error::msg(format!(
+                        "Line {} is not `action = chord`",
+                        number + 1
+                    )))
+                },
+                |e| Ok(e),
+            )?;
+            let action = A::from_name(name.trim()).map_or_else(
+                || {
+                    Err(Error::msg(format!(
+                        "Line {} has an unknown action \"{}\"",
+                        number + 1,
+                        name.trim()
+                    )))
+                },
+                |e| Ok(e),
+            )?;
+            let chord = chord
+                .parse::<Chord>()
+                .map_err(|err| err.context(format!("Line {}", number + 1)))?;
+            bindings.bind_scoped(action, chord, scope.clone());
+        }
+        Ok(bindings)
+    }
+
+    /// Writes the bindings in the format [`ActionBindings::from_config`] reads. Fails for
+    /// chords using keys that cannot be named in a file.
+    pub fn to_config(&self) -> anyhow::Result<String> {
+        let mut config = String::new();
+        let mut scopes = Vec::<Option<&str>>::new();
+        for binding in &self.bindings {
+            if !scopes.contains(&binding.scope.as_deref()) {
+                scopes.push(binding.scope.as_deref());
+            }
+        }
+        // Global bindings have to come before the first heading.
+        scopes.sort_by_key(|scope| scope.is_some());
+
+        for scope in scopes {
+            if let Some(scope) = scope {
+                config.push_str(&format!("\n[{}]\n", scope));
+            }
+            for binding in self
+                .bindings
+                .iter()
+                .filter(|binding| binding.scope.as_deref() == scope)
+            {
+                let chord = binding.chord.to_string();
+                if chord.parse::<Chord>().ok() != Some(binding.chord) {
+                    return Err(Error::msg(format!(
+                        "The chord of {} cannot be saved",
+                        binding.action.name()
+                    )));
+                }
+                config.push_str(&format!("{} = {}\n", binding.action.name(), chord));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_config(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        Ok(fs::write(path, self.to_config()?)?)
+    }
+}
+
+impl<A: Action> Default for ActionBindings<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The scope a window's input is matched against [`ActionBindings`] in.
+#[derive(Component, Debug, Clone, Default)]
+pub struct InputScope(pub Option<String>);
+
+/// Adds [`ActionBindings<A>`] and sends [`ActionTriggered<A>`] events.
+#[derive(Debug)]
+pub struct ActionPlugin<A: Action>(PhantomData<A>);
+
+impl<A: Action> EcsPlugin for ActionPlugin<A> {
+    fn build(world: &mut World, schedule: &mut Schedule) {
+        world.init_resource::<ActionBindings<A>>();
+        world.init_resource::<Events<ActionTriggered<A>>>();
+
+        schedule.stage(CoreStages::EventUpdate, |stage: &mut SystemStage| {
+            stage.add_system(Events::<ActionTriggered<A>>::update_system);
+            stage
+        });
+        schedule.stage(CoreStages::PreUpdate, |stage: &mut SystemStage| {
+            stage.add_system(trigger_actions_system::<A>);
+            stage
+        });
+    }
+}
+
+pub(crate) fn trigger_actions_system<A: Action>(
+    bindings: Res<ActionBindings<A>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut mouse_events: EventReader<MouseInput>,
+    scopes: Query<&InputScope>,
+    mut actions: EventWriter<ActionTriggered<A>>,
+) {
+    assert_is_system(trigger_actions_system::<A>);
+    tracing::debug_span!("trigger_actions_system");
+    let pressed_keys = keyboard_events
+        .iter()
+        .filter(|event| event.is_pressed() && !event.repeat && !event.is_synthetic)
+        .map(|event| {
+            (
+                event.entity,
+                event.modifiers,
+                ChordInput::Key(event.physical_key),
+            )
+        });
+    let pressed_buttons = mouse_events
+        .iter()
+        .filter(|event| event.is_pressed())
+        .map(|event| {
+            (
+                event.entity,
+                event.modifiers,
+                ChordInput::Mouse(event.button),
+            )
+        });
+
+    for (entity, modifiers, input) in pressed_keys.chain(pressed_buttons) {
+        let scope = scopes.get(entity).ok().and_then(|scope| scope.0.as_deref());
+        for action in bindings.triggered(modifiers, input, scope) {
+            actions.send(ActionTriggered { entity, action });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum TestAction {
+        Save,
+        Select,
+    }
+
+    impl Action for TestAction {
+        fn name(&self) -> &'static str {
+            match self {
+                TestAction::Save => "save",
+                TestAction::Select => "select",
+            }
+        }
+
+        fn from_name(name: &str) -> Option<Self> {
+            match name {
+                "save" => Some(TestAction::Save),
+                "select" => Some(TestAction::Select),
+                _ => None,
+            }
+        }
+    }
+
+    fn ctrl_s() -> Chord {
+        Chord::key(ModifiersState::CONTROL, KeyCode::KeyS)
+    }
+
+    #[test]
+    fn chords_round_trip_through_display() {
+        for chord in [
+            "Ctrl+Shift+S",
+            "Alt+MouseLeft",
+            "Super+1",
+            "Mouse4",
+            "Ctrl+Alt+Shift+Super+MouseMiddle",
+        ] {
+            assert_eq!(chord.parse::<Chord>().unwrap().to_string(), chord);
+        }
+    }
+
+    #[test]
+    fn chords_parse_aliases_in_any_case() {
+        assert_eq!(
+            "control + SHIFT + s".parse::<Chord>().unwrap(),
+            Chord::key(
+                ModifiersState::CONTROL | ModifiersState::SHIFT,
+                KeyCode::KeyS
+            )
+        );
+        assert_eq!(
+            "cmd+option+mouseright".parse::<Chord>().unwrap(),
+            Chord::mouse(
+                ModifiersState::SUPER | ModifiersState::ALT,
+                MouseButton::Right
+            )
+        );
+    }
+
+    #[test]
+    fn malformed_chords_are_rejected() {
+        for chord in ["", "Ctrl+", "Ctrl", "S+Ctrl", "Ctrl+NotAKey"] {
+            assert!(chord.parse::<Chord>().is_err(), "{:?} parsed", chord);
+        }
+    }
+
+    #[test]
+    fn cmd_or_ctrl_is_the_platform_shortcut_modifier() {
+        let modifier = if cfg!(target_os = "macos") {
+            ModifiersState::SUPER
+        } else {
+            ModifiersState::CONTROL
+        };
+        assert_eq!(
+            "CmdOrCtrl+S".parse::<Chord>().unwrap(),
+            Chord::key(modifier, KeyCode::KeyS)
+        );
+    }
+
+    #[test]
+    fn scoped_bindings_hide_global_ones() {
+        let mut bindings = ActionBindings::new();
+        bindings.bind(TestAction::Save, ctrl_s());
+        bindings.bind_scoped(TestAction::Select, ctrl_s(), Some("editor"));
+
+        let triggered = |scope: Option<&'static str>| {
+            bindings
+                .triggered(
+                    ModifiersState::CONTROL,
+                    ChordInput::Key(KeyCode::KeyS),
+                    scope,
+                )
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(triggered(Some("editor")), [TestAction::Select]);
+        assert_eq!(triggered(Some("viewer")), [TestAction::Save]);
+        assert_eq!(triggered(None), [TestAction::Save]);
+    }
+
+    #[test]
+    fn rebind_replaces_chords_in_one_scope() {
+        let ctrl_w = Chord::key(ModifiersState::CONTROL, KeyCode::KeyW);
+        let mut bindings = ActionBindings::new();
+        bindings.bind(TestAction::Save, ctrl_s());
+        bindings.bind(
+            TestAction::Save,
+            Chord::key(
+                ModifiersState::CONTROL | ModifiersState::SHIFT,
+                KeyCode::KeyS,
+            ),
+        );
+        bindings.bind_scoped(TestAction::Save, ctrl_s(), Some("editor"));
+
+        bindings.rebind(TestAction::Save, ctrl_w, None::<String>);
+        let global = bindings
+            .bindings()
+            .iter()
+            .filter(|binding| binding.scope.is_none())
+            .map(|binding| binding.chord)
+            .collect::<Vec<_>>();
+        assert_eq!(global, [ctrl_w]);
+        assert_eq!(bindings.chords(TestAction::Save).count(), 2);
+
+        bindings.unbind(TestAction::Save);
+        assert!(bindings.bindings().is_empty());
+    }
+
+    #[test]
+    fn binding_the_same_chord_again_replaces_its_action() {
+        let mut bindings = ActionBindings::new();
+        bindings.bind(TestAction::Save, ctrl_s());
+        bindings.bind(TestAction::Select, ctrl_s());
+        assert_eq!(bindings.chords(TestAction::Save).count(), 0);
+        assert_eq!(bindings.chords(TestAction::Select).count(), 1);
+    }
+
+    #[test]
+    fn config_round_trips_with_scopes() {
+        let config = "save = Ctrl+S\n\n[editor]\nselect = Ctrl+MouseLeft\nsave = Alt+S\n";
+        let bindings = ActionBindings::<TestAction>::from_config(config).unwrap();
+        assert_eq!(
+            bindings.bindings()[1],
+            Binding {
+                action: TestAction::Select,
+                chord: Chord::mouse(ModifiersState::CONTROL, MouseButton::Left),
+                scope: Some("editor".to_string()),
+            }
+        );
+        assert_eq!(bindings.to_config().unwrap(), config);
+    }
+
+    #[test]
+    fn config_skips_comments_and_blank_lines() {
+        let config = "# Global bindings\n\n  save = Ctrl+S  \n";
+        let bindings = ActionBindings::<TestAction>::from_config(config).unwrap();
+        assert_eq!(
+            bindings.bindings(),
+            [Binding {
+                action: TestAction::Save,
+                chord: ctrl_s(),
+                scope: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn config_errors_name_the_line() {
+        let error = |config| {
+            ActionBindings::<TestAction>::from_config(config)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("save = Ctrl+S\nsave Ctrl+S"),
+            "Line 2 is not `action = chord`"
+        );
+        assert_eq!(
+            error("\n[editor]\nopen = Ctrl+O"),
+            "Line 3 has an unknown action \"open\""
+        );
+        assert_eq!(error("save = Ctrl+"), "Line 1");
+    }
+}
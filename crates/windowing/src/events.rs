@@ -1,25 +1,38 @@
-use crate::window::{
-    self, OsWindow, Window, WindowBundle, WindowCallbacks, WindowCallbacksManager, WindowContext,
-    WindowId,
+use crate::{
+    input::WindowInputEvent,
+    window::{
+        self, HeadlessWindow, HeadlessWindowBundle, OsWindow, Theme, Window, WindowBundle,
+        WindowCallbacks, WindowCallbacksManager, WindowContext, WindowDescriptor, WindowId,
+        WindowParent,
+    },
+    QuitPolicy, ShutdownEventLoop,
 };
 use bevy_ecs::{
     event::Events,
     prelude::{
-        Added, Commands, Entity, EventReader, EventWriter, Mut, NonSend, Query, ResMut, With, World,
+        Added, Changed, Commands, Entity, EventReader, EventWriter, Mut, NonSend, Or, Query, Res,
+        ResMut, With, World,
     },
     system::{assert_is_system, SystemState},
 };
 use derive_deref::{Deref, DerefMut};
-use renderer::{Surface, WindowDrawTarget};
+use renderer::{
+    elements::{Clip, DrawImage, DrawShape, DrawText, Opacity, RenderElements, Transform},
+    painter::{Point, Rect},
+    Damage, DriverPreference, Frame, FrameStore, HeadlessRendering, RenderElementTree, Surface,
+    WindowDrawTarget,
+};
 use smallvec::SmallVec;
 use tao::event_loop::EventLoopWindowTarget;
 use utilities::tracing;
 
 pub const WINDOW_LIFECYCLE_SYSTEMS: &str = "window-lifecycle";
+pub const PRESENT_WINDOWS_SYSTEMS: &str = "present-windows";
 #[derive(Debug)]
 pub struct Create {
     pub window: Box<dyn Window>,
     pub window_id: WindowId,
+    pub descriptor: WindowDescriptor,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -28,15 +41,88 @@ pub struct Resize {
     pub entity: Entity,
 }
 
+/// The window moved to a monitor with a different scale factor, or the scale factor
+/// of its monitor changed.
+#[derive(Debug, Copy, Clone)]
+pub struct ScaleFactorChanged {
+    pub window_id: WindowId,
+    pub entity: Entity,
+    pub scale_factor: f64,
+}
+
+/// The window gained or lost keyboard focus.
+#[derive(Debug, Copy, Clone)]
+pub struct Focused {
+    pub entity: Entity,
+    pub focused: bool,
+}
+
+/// The window moved on the desktop, `position` is its top left corner in logical units.
+#[derive(Debug, Copy, Clone)]
+pub struct Moved {
+    pub entity: Entity,
+    pub position: Point,
+}
+
+/// The window was minimized or brought back. **Only sent on Windows.**
+///
+/// tao has neither an event nor a way to ask whether a window is minimized, so it is
+/// inferred from Windows resizing minimized windows to nothing. Other platforms keep the
+/// size and never send this. Occlusion by other windows is not reported on any platform.
+#[derive(Debug, Copy, Clone)]
+pub struct Minimized {
+    pub entity: Entity,
+    pub minimized: bool,
+}
+
+/// The OS switched between its light and dark theme.
+#[derive(Debug, Copy, Clone)]
+pub struct ThemeChanged {
+    pub entity: Entity,
+    pub theme: Theme,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Destroy(pub Entity);
 
-#[derive(Debug, Copy, Clone, Deref, DerefMut)]
-pub struct Repaint(pub Entity);
+#[derive(Debug, Copy, Clone)]
+pub struct Repaint {
+    pub entity: Entity,
+    /// The part of the window to redraw and present in logical units, `None` for all of it.
+    pub area: Option<Rect>,
+}
+
+impl Repaint {
+    pub fn all(entity: Entity) -> Self {
+        Self { entity, area: None }
+    }
+
+    pub fn area(entity: Entity, area: impl Into<Rect>) -> Self {
+        Self {
+            entity,
+            area: Some(area.into()),
+        }
+    }
+
+    pub fn damage(&self) -> Damage {
+        self.area.map_or(Damage::Full, Damage::Area)
+    }
+}
 
 #[derive(Debug, Copy, Clone, Deref, DerefMut)]
 pub struct CloseRequested(pub Entity);
 
+/// Asks for the contents of a window's surface to be read back once it has been presented,
+/// the result is sent as a [`FrameCaptured`] event.
+#[derive(Debug, Copy, Clone, Deref, DerefMut)]
+pub struct CaptureFrame(pub Entity);
+
+#[derive(Debug, Clone)]
+pub struct FrameCaptured {
+    pub entity: Entity,
+    pub frame: Frame,
+}
+
 macro_rules! impl_event {
    ($($TYPE:ty),*) => {
       $(
@@ -45,13 +131,78 @@ macro_rules! impl_event {
    }
 }
 
-impl_event!(Create, Resize, Repaint, Destroy, CloseRequested);
+impl_event!(
+    Create,
+    Resize,
+    ScaleFactorChanged,
+    Focused,
+    Moved,
+    Minimized,
+    ThemeChanged,
+    Repaint,
+    Destroy,
+    CloseRequested,
+    CaptureFrame,
+    FrameCaptured
+);
+
+impl WindowInputEvent for ScaleFactorChanged {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.scale_factor_changed(ctx, self.scale_factor);
+    }
+}
+
+impl WindowInputEvent for Focused {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.focused(ctx, self.focused);
+    }
+}
+
+impl WindowInputEvent for Moved {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.moved(ctx, self.position);
+    }
+}
+
+impl WindowInputEvent for Minimized {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.minimized(ctx, self.minimized);
+    }
+}
+
+impl WindowInputEvent for ThemeChanged {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.theme_changed(ctx, self.theme);
+    }
+}
 
 pub(crate) type CreateWindowSystemState<'w, 's> = SystemState<(
     Query<'w, 's, (Entity, &'static WindowId), With<window::Marker>>,
-    NonSend<'w, EventLoopWindowTarget<()>>,
+    Option<NonSend<'w, EventLoopWindowTarget<()>>>,
+    Option<Res<'w, HeadlessRendering>>,
     ResMut<'w, Events<Create>>,
 )>;
+
+/// Headless windows are spawned with a different bundle, without an OS window.
+enum NewWindowBundle {
+    Os(WindowBundle),
+    Headless(HeadlessWindowBundle),
+}
 pub(crate) fn create_window_system(world: &mut World) {
     // Guard to prevent unessersary work from being done.
     if world.get_resource::<Events<Create>>().unwrap().is_empty() {
@@ -60,10 +211,15 @@ pub(crate) fn create_window_system(world: &mut World) {
 
     tracing::debug_span!("create_window_system");
     world.resource_scope(|world, mut state: Mut<CreateWindowSystemState>| {
-        let (window_id_query, event_loop, mut events) = state.get_mut(world);
+        let (window_id_query, event_loop, headless, mut events) = state.get_mut(world);
         let bundles = events
             .drain()
-            .map(|Create { window, window_id }| {
+            .map(|create| {
+                let Create {
+                    window,
+                    window_id,
+                    descriptor,
+                } = create;
                 #[cfg(debug_assertions)]
                 if window_id_query
                     .iter()
@@ -72,20 +228,49 @@ pub(crate) fn create_window_system(world: &mut World) {
                 {
                     panic!("Window already existed with {:?}", window_id);
                 }
-                match WindowBundle::new(
-                    window_id,
-                    &*event_loop,
-                    // TODO make this the windows root widget entity
-                    Entity::from_raw(0),
-                ) {
-                    Ok(window_bundle) => (window_bundle, window),
+                // TODO make this the windows root widget entity
+                let root = Entity::from_raw(0);
+                // Headless windows need no event loop, so they can be created without
+                // a display.
+                if let Some(ref headless) = headless {
+                    let bundle = HeadlessWindowBundle::new(
+                        window_id,
+                        root,
+                        &descriptor,
+                        headless.scale_factor,
+                    );
+                    return (NewWindowBundle::Headless(bundle), window, descriptor);
+                }
+                let event_loop = event_loop
+                    .as_deref()
+                    .expect("No EventLoopWindowTarget to create windows with");
+                match WindowBundle::new(window_id, event_loop, root, &descriptor) {
+                    Ok(window_bundle) => (NewWindowBundle::Os(window_bundle), window, descriptor),
                     Err(err) => panic!("{}", err),
                 }
             })
             .collect::<SmallVec<[_; 4]>>();
 
-        for (bundle, callbacks) in bundles {
-            let entity = world.spawn().insert_bundle(bundle).id();
+        for (bundle, callbacks, descriptor) in bundles {
+            let entity = match bundle {
+                NewWindowBundle::Os(bundle) => world.spawn().insert_bundle(bundle).id(),
+                NewWindowBundle::Headless(bundle) => world.spawn().insert_bundle(bundle).id(),
+            };
+
+            // The parent may have been created in this same batch, so it is looked up
+            // after spawning
+            if let Some(parent_id) = descriptor.parent {
+                match window::find_window_entity(world, parent_id) {
+                    Some(parent) => {
+                        world.entity_mut(entity).insert(WindowParent {
+                            parent,
+                            parent_id,
+                            modal: descriptor.modal,
+                        });
+                    }
+                    None => tracing::warn!("Parent window {:?} does not exist", parent_id),
+                }
+            }
 
             callbacks.on_create(WindowContext { entity, world });
 
@@ -110,21 +295,66 @@ pub(crate) fn destroy_window_system(world: &mut World) {
     tracing::debug_span!("destroy_window_system");
     world.resource_scope(|world, mut state: Mut<DestroyWindowSystemState>| {
         let (_, mut events) = state.get_mut(world);
-        let entities = events
+        let closed = events
             .iter()
             .map(|Destroy(entity)| *entity)
             .collect::<SmallVec<[_; 4]>>();
+        // Children are destroyed along with their parent, before it
+        let mut entities = SmallVec::<[Entity; 4]>::new();
+        for entity in closed {
+            window::collect_window_tree(world, entity, &mut entities);
+        }
+
+        let mut destroyed_ids = SmallVec::<[WindowId; 4]>::new();
         for entity in entities {
+            // The window may already have been destroyed along with its parent
+            if !world
+                .get_resource::<WindowCallbacksManager>()
+                .map_or(false, |window_callbacks| window_callbacks.contains(entity))
+            {
+                continue;
+            }
             world.resource_scope(|world, mut window_callbacks: Mut<WindowCallbacksManager>| {
                 let callbacks = window_callbacks
                     .get_mut(entity)
                     .expect("No callbacks found for this entity");
                 callbacks.on_destroyed(WindowContext { entity, world });
+                window_callbacks.remove(entity);
             });
+            if let Some(&window_id) = world.get::<WindowId>(entity) {
+                destroyed_ids.push(window_id);
+            }
             let (mut commands, _) = state.get_mut(world);
             commands.entity(entity).despawn();
         }
         state.apply(world);
+
+        let policy = world
+            .get_resource::<QuitPolicy>()
+            .copied()
+            .unwrap_or_default();
+        let quit = match policy {
+            // Windows waiting in `Events<Create>` are still to be opened, they are drained
+            // as soon as they are created.
+            QuitPolicy::LastWindowClosed => {
+                world
+                    .query_filtered::<Entity, With<window::Marker>>()
+                    .iter(world)
+                    .next()
+                    .is_none()
+                    && world
+                        .get_resource::<Events<Create>>()
+                        .map_or(true, |events| events.is_empty())
+            }
+            QuitPolicy::MainWindowClosed(main) => destroyed_ids.contains(&main),
+            QuitPolicy::Manual => false,
+        };
+        if quit {
+            world
+                .get_resource_mut::<Events<ShutdownEventLoop>>()
+                .unwrap_or_else(|| panic!("No EvenEvents<ShutdownEventLoop> Resource"))
+                .send_default();
+        }
     });
 }
 
@@ -175,12 +405,24 @@ pub(crate) fn create_surface_for_window_system(
     mut commands: Commands,
     new_windows: Query<Entity, Added<window::Marker>>,
     mut events: EventReader<Resize>,
+    mut scale_factor_events: EventReader<ScaleFactorChanged>,
     mut redraw_events: EventWriter<Repaint>,
-    mut window_query: Query<&mut OsWindow, With<window::Marker>>,
+    mut window_query: Query<
+        (
+            Option<&OsWindow>,
+            Option<&HeadlessWindow>,
+            Option<&mut Surface>,
+        ),
+        With<window::Marker>,
+    >,
+    headless: Option<Res<HeadlessRendering>>,
+    preference: Option<Res<DriverPreference>>,
 ) {
     assert_is_system(create_surface_for_window_system);
     tracing::debug_span!("create_surface_for_window_system");
-    let windows_to_create_surfaces_for = events
+    // A new window can be resized before its surface has been inserted
+    let mut windows_to_create_surfaces_for = SmallVec::<[Entity; 4]>::new();
+    for entity in events
         .iter()
         .map(
             |Resize {
@@ -188,44 +430,222 @@ pub(crate) fn create_surface_for_window_system(
                  entity,
              }| *entity,
         )
-        .chain(new_windows.iter());
+        .chain(scale_factor_events.iter().map(|event| event.entity))
+        .chain(new_windows.iter())
+    {
+        if !windows_to_create_surfaces_for.contains(&entity) {
+            windows_to_create_surfaces_for.push(entity);
+        }
+    }
 
     for entity in windows_to_create_surfaces_for {
-        let os_window = match window_query.get_mut(entity) {
-            Ok(os_window) => os_window,
+        let (os_window, headless_window, old_surface) = match window_query.get_mut(entity) {
+            Ok(window) => window,
             Err(err) => panic!("{}", err),
         };
+        let ((width, height), scale_factor) = match (os_window, headless_window) {
+            (Some(os_window), _) => (
+                os_window.get_draw_bounds(),
+                WindowDrawTarget::scale_factor(os_window),
+            ),
+            (None, Some(headless_window)) => {
+                (headless_window.size(), headless_window.scale_factor())
+            }
+            (None, None) => continue,
+        };
 
         // if the bounds are 0 then keep the old surface as skia will panic if
         // a surface is created with 0 bounds
-        let (width, height) = os_window.get_draw_bounds();
         if width <= 0 || height <= 0 {
             continue;
         }
 
-        let surface = match Surface::new_cpu(&*os_window) {
+        // Existing surfaces keep their driver and are only resized, which also picks
+        // up the new scale factor
+        if let Some(mut surface) = old_surface {
+            let resized = match os_window {
+                Some(os_window) => surface.resize(os_window),
+                None => surface.resize_headless(width, height, scale_factor as f32),
+            };
+            match resized {
+                Ok(Damage::Area(exposed)) => redraw_events.send(Repaint::area(entity, exposed)),
+                // Shrinking exposes nothing, but the smaller surface still has to be
+                // presented.
+                Ok(_) => redraw_events.send(Repaint::all(entity)),
+                Err(err) => panic!("{}", err),
+            }
+            continue;
+        }
+
+        let surface = match (os_window, headless.as_deref()) {
+            (Some(os_window), None) => {
+                let preference = preference.as_deref().copied().unwrap_or_default();
+                Surface::new(os_window, preference)
+            }
+            // Windows without an OS window can only ever be presented headless
+            (_, headless) => Surface::new_headless(
+                width,
+                height,
+                FrameStore::with_capacity(headless.copied().unwrap_or_default().frame_capacity),
+            )
+            .map(|mut surface| {
+                surface.set_scale_factor(scale_factor as f32);
+                surface
+            }),
+        };
+        let surface = match surface {
             Ok(new_surface) => new_surface,
             Err(err) => panic!("{}", err),
         };
         commands.entity(entity).insert(surface);
-        redraw_events.send(Repaint(entity));
+        redraw_events.send(Repaint::all(entity));
+    }
+}
+
+pub(crate) type PaintWindowsSystemState<'w, 's> = SystemState<EventReader<'w, 's, Repaint>>;
+
+pub(crate) fn paint_windows_system(world: &mut World) {
+    // Guard to prevent unessersary work from being done.
+    if world.get_resource::<Events<Repaint>>().unwrap().is_empty() {
+        return;
+    }
+
+    tracing::debug_span!("paint_windows_system");
+    world.resource_scope(|world, mut state: Mut<PaintWindowsSystemState>| {
+        let mut events = state.get_mut(world);
+        let mut windows = SmallVec::<[(Entity, Damage); 4]>::new();
+        for event in events.iter() {
+            match windows
+                .iter_mut()
+                .find(|(entity, _)| *entity == event.entity)
+            {
+                Some((_, damage)) => damage.add(event.damage()),
+                None => windows.push((event.entity, event.damage())),
+            }
+        }
+
+        world.resource_scope(|world, mut window_callbacks: Mut<WindowCallbacksManager>| {
+            for (entity, damage) in windows {
+                // Windows that have not been given a surface yet have nothing to paint into.
+                let mut surface = match world.get_mut::<Surface>(entity) {
+                    Some(surface) => surface,
+                    None => continue,
+                };
+                surface.begin_paint(damage);
+                if let Some(callbacks) = window_callbacks.get_mut(entity) {
+                    callbacks.paint(&mut surface);
+                }
+            }
+        });
+        state.apply(world);
+    });
+}
+
+/// Draws each repainted window's [`RenderElementTree`] on top of what [`Window::paint`] drew.
+pub fn render_element_trees_system(
+    mut events: EventReader<Repaint>,
+    mut windows: Query<(&mut Surface, &RenderElementTree), With<window::Marker>>,
+    elements: RenderElements,
+) {
+    assert_is_system(render_element_trees_system);
+    tracing::debug_span!("render_element_trees_system");
+    let mut entities = SmallVec::<[Entity; 4]>::new();
+    for entity in events.iter().map(|event| event.entity) {
+        if !entities.contains(&entity) {
+            entities.push(entity);
+        }
+    }
+
+    for entity in entities {
+        if let Ok((mut surface, tree)) = windows.get_mut(entity) {
+            tree.render(&mut surface, &elements);
+        }
+    }
+}
+
+/// Repaints windows whose [`RenderElementTree`], or any element in it, changed this frame.
+pub fn repaint_changed_elements_system(
+    mut repaint: EventWriter<Repaint>,
+    changed_trees: Query<Entity, (With<window::Marker>, Changed<RenderElementTree>)>,
+    trees: Query<(Entity, &RenderElementTree), With<window::Marker>>,
+    changed_elements: Query<
+        Entity,
+        Or<(
+            Changed<Transform>,
+            Changed<Clip>,
+            Changed<Opacity>,
+            Changed<DrawShape>,
+            Changed<DrawText>,
+            Changed<DrawImage>,
+        )>,
+    >,
+) {
+    assert_is_system(repaint_changed_elements_system);
+    tracing::debug_span!("repaint_changed_elements_system");
+    for (entity, tree) in trees.iter() {
+        let is_changed = changed_trees.contains(entity)
+            || changed_elements
+                .iter()
+                .any(|element| tree.contains(element));
+        if is_changed {
+            repaint.send(Repaint::all(entity));
+        }
     }
 }
 
 pub fn repaint_and_present_windows(
     mut events: EventReader<Repaint>,
-    mut windows: Query<(&mut OsWindow, &mut Surface), With<window::Marker>>,
+    mut windows: Query<(Option<&OsWindow>, &mut Surface), With<window::Marker>>,
 ) {
     assert_is_system(repaint_and_present_windows);
     tracing::debug_span!("repaint_and_present_windows");
-    for entity in events.iter().map(|&event| *event) {
+    // Every window is presented once, with all the damage painted this frame.
+    let mut entities = SmallVec::<[Entity; 4]>::new();
+    for entity in events.iter().map(|event| event.entity) {
+        if !entities.contains(&entity) {
+            entities.push(entity);
+        }
+    }
+
+    for entity in entities {
         let (os_window, mut surface) = match windows.get_mut(entity) {
             Ok((os_window, surface)) => (os_window, surface),
             Err(_) => continue,
         };
-        match surface.present_surface(&*os_window) {
+        // Headless windows have nothing to present to, their frames are recorded
+        let presented = match os_window {
+            Some(os_window) => surface.present_surface(os_window),
+            None => surface.present_headless(),
+        };
+        match presented {
             Ok(_) => {}
             Err(err) => panic!("{}", err),
         };
     }
 }
+
+pub fn capture_frames_system(
+    mut events: EventReader<CaptureFrame>,
+    mut captured: EventWriter<FrameCaptured>,
+    mut surfaces: Query<&mut Surface, With<window::Marker>>,
+) {
+    assert_is_system(capture_frames_system);
+    tracing::debug_span!("capture_frames_system");
+    let mut entities = SmallVec::<[Entity; 4]>::new();
+    for entity in events.iter().map(|&event| *event) {
+        if !entities.contains(&entity) {
+            entities.push(entity);
+        }
+    }
+
+    for entity in entities {
+        let mut surface = match surfaces.get_mut(entity) {
+            Ok(surface) => surface,
+            Err(_) => continue,
+        };
+        match surface.capture() {
+            Ok(frame) => captured.send(FrameCaptured { entity, frame }),
+            Err(err) => tracing::error!("Unable to capture {:?}: {}", entity, err),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Input from the OS, sent as events aimed at the window entity that received it.
+//!
+//! Every event is also handed to the matching [`Window`](crate::window::Window) callback
+//! in `CoreStages::PreUpdate`.
+//!
+//! Text fields should listen to [`ReceivedCharacter`], which covers both typed and input
+//! method text.
+//!
+//! There is no preedit event. No tao release reports input method composition text, only
+//! the final [`ImeCommit`], so the OS input method draws the text being composed in its
+//! own window. Keep that window next to the text cursor with
+//! [`WindowContext::set_ime_position`](crate::window::WindowContext::set_ime_position).
+
+use bevy_ecs::{
+    event::Events,
+    prelude::{Component, Entity, EventReader, IntoExclusiveSystem, Mut, World},
+    schedule::{Schedule, SystemSet, SystemStage},
+    system::SystemState,
+};
+use derive_deref::{Deref, DerefMut};
+use renderer::painter::Point;
+use smallvec::SmallVec;
+pub use tao::{
+    event::{ElementState, MouseButton, TouchPhase},
+    keyboard::{Key, KeyCode, KeyLocation, ModifiersState},
+};
+use utilities::{tracing, CoreStages, Event};
+
+use crate::{
+    events::WINDOW_LIFECYCLE_SYSTEMS,
+    window::{WindowCallbacks, WindowCallbacksManager, WindowContext},
+};
+
+/// A key was pressed or released while the window had focus.
+#[derive(Debug, Clone)]
+pub struct KeyboardInput {
+    pub entity: Entity,
+    /// The key's position on the keyboard, ignoring the layout. Use this for shortcuts
+    /// that should stay in place, like WASD.
+    pub physical_key: KeyCode,
+    /// The key after the layout and modifiers are applied.
+    pub logical_key: Key<'static>,
+    /// The text the key produces, `None` for keys like arrows and for releases.
+    pub text: Option<&'static str>,
+    pub location: KeyLocation,
+    pub state: ElementState,
+    /// The key is held down and the OS is repeating it.
+    pub repeat: bool,
+    /// The modifiers held when the key changed.
+    pub modifiers: ModifiersState,
+    /// Sent by tao for keys that were already held when the window gained focus, or
+    /// released when it lost focus.
+    pub is_synthetic: bool,
+}
+
+impl KeyboardInput {
+    pub fn is_pressed(&self) -> bool {
+        self.state == ElementState::Pressed
+    }
+}
+
+/// The modifiers held on a window changed.
+#[derive(Debug, Copy, Clone)]
+pub struct ModifiersChanged {
+    pub entity: Entity,
+    pub modifiers: ModifiersState,
+}
+
+/// The cursor moved over the window. Positions are in logical units from the top left
+/// corner of the window.
+#[derive(Debug, Copy, Clone)]
+pub struct CursorMoved {
+    pub entity: Entity,
+    pub position: Point,
+}
+
+/// The cursor moved onto the window. tao does not say where, so this is the last position
+/// it was seen at over the window.
+#[derive(Debug, Copy, Clone)]
+pub struct CursorEntered {
+    pub entity: Entity,
+    pub position: Point,
+}
+
+/// The cursor left the window, at the last position it was seen at.
+#[derive(Debug, Copy, Clone)]
+pub struct CursorLeft {
+    pub entity: Entity,
+    pub position: Point,
+}
+
+/// A mouse button was pressed or released over the window.
+#[derive(Debug, Copy, Clone)]
+pub struct MouseInput {
+    pub entity: Entity,
+    pub button: MouseButton,
+    pub state: ElementState,
+    /// Where the cursor is, or was last seen over the window while it is outside.
+    pub position: Point,
+    pub modifiers: ModifiersState,
+}
+
+impl MouseInput {
+    pub fn is_pressed(&self) -> bool {
+        self.state == ElementState::Pressed
+    }
+}
+
+/// How far a [`MouseWheel`] scrolled.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ScrollDelta {
+    /// Lines or rows to scroll, as sent by most mouse wheels.
+    Lines { x: f32, y: f32 },
+    /// Logical units to scroll, as sent by touchpads.
+    Pixels { x: f32, y: f32 },
+}
+
+/// The mouse wheel or touchpad scrolled over the window. Positive values scroll up
+/// and to the left.
+#[derive(Debug, Copy, Clone)]
+pub struct MouseWheel {
+    pub entity: Entity,
+    pub delta: ScrollDelta,
+    /// Touchpads report when a scroll gesture starts and ends, mouse wheels are always
+    /// `TouchPhase::Moved`.
+    pub phase: TouchPhase,
+    /// Where the cursor is, or was last seen over the window while it is outside.
+    pub position: Point,
+    pub modifiers: ModifiersState,
+}
+
+/// A finger or pen touched, moved on, or lifted from the window. Pens are reported the
+/// same way as fingers, with a pressure when the platform knows it.
+///
+/// The first touch on a window while no other touch is down is its primary touch, which
+/// is also sent as left mouse button and cursor events so mouse driven code works on
+/// touchscreens. The emulated cursor enters the window when the touch starts and leaves
+/// it when the touch ends or is cancelled.
+///
+/// tao does not report pen tilt or azimuth, only the altitude some platforms provide, so
+/// there are no tilt fields.
+#[derive(Debug, Copy, Clone)]
+pub struct TouchInput {
+    pub entity: Entity,
+    /// Identifies one finger or pen from `TouchPhase::Started` until it ends.
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub position: Point,
+    /// From 0.0 to 1.0, `None` when the platform does not report it.
+    pub pressure: Option<f32>,
+    /// The angle of a pen to the screen in radians, pi/2 when it is upright. `None`
+    /// when the platform does not report it.
+    pub altitude: Option<f32>,
+    /// The touch is also being sent as mouse events.
+    pub is_primary: bool,
+}
+
+/// A character typed into the window, either by a key press or committed by the input
+/// method. Control characters and keys pressed with Ctrl or Super held are left out, so
+/// shortcuts do not insert text.
+#[derive(Debug, Copy, Clone)]
+pub struct ReceivedCharacter {
+    pub entity: Entity,
+    pub character: char,
+}
+
+/// The input method finished composing `text`, e.g. after a CJK candidate was picked.
+/// The text being composed before this is not reported, see the [module docs](self).
+/// Only sent while the input method is enabled, see
+/// [`WindowContext::set_ime_enabled`](crate::window::WindowContext::set_ime_enabled).
+#[derive(Debug, Clone)]
+pub struct ImeCommit {
+    pub entity: Entity,
+    pub text: String,
+}
+
+impl Event for KeyboardInput {}
+impl Event for ModifiersChanged {}
+impl Event for CursorMoved {}
+impl Event for CursorEntered {}
+impl Event for CursorLeft {}
+impl Event for MouseInput {}
+impl Event for MouseWheel {}
+impl Event for TouchInput {}
+impl Event for ReceivedCharacter {}
+impl Event for ImeCommit {}
+
+/// The modifiers currently held on a window.
+#[derive(Component, Debug, Copy, Clone, Default, Deref, DerefMut)]
+pub struct Modifiers(pub ModifiersState);
+
+/// Where the cursor is over a window in logical units, `None` while it is outside.
+#[derive(Component, Debug, Copy, Clone, Default, Deref, DerefMut)]
+pub struct CursorPosition(pub Option<Point>);
+
+/// Where the cursor was last seen over a window. Unlike [`CursorPosition`] it is kept
+/// after the cursor leaves, so events sent while it is outside, like releasing a drag,
+/// still have a position.
+#[derive(Component, Debug, Copy, Clone, Default)]
+pub(crate) struct LastCursorPosition(pub(crate) Point);
+
+/// Whether a window accepts input method text.
+#[derive(Component, Debug, Copy, Clone)]
+pub(crate) struct ImeEnabled(pub(crate) bool);
+
+impl Default for ImeEnabled {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// The touch on a window that is sent as mouse events.
+#[derive(Component, Debug, Copy, Clone, Default)]
+pub(crate) struct PrimaryTouch {
+    id: Option<u64>,
+    /// Whether the touch brought the emulated cursor into the window, in which case it
+    /// leaves again when the touch ends. A real mouse over the window stays there.
+    pub(crate) entered_window: bool,
+}
+
+impl PrimaryTouch {
+    /// Follows touch `id` moving to `phase`, returns whether it is the primary touch.
+    pub(crate) fn track(&mut self, id: u64, phase: TouchPhase) -> bool {
+        match (phase, self.id) {
+            (TouchPhase::Started, None) => {
+                self.id = Some(id);
+                true
+            }
+            (TouchPhase::Ended | TouchPhase::Cancelled, Some(primary)) if primary == id => {
+                self.id = None;
+                true
+            }
+            (_, Some(primary)) => primary == id,
+            (_, None) => false,
+        }
+    }
+}
+
+/// An event aimed at one window, which its callbacks are told about.
+pub(crate) trait WindowInputEvent: Event + Clone {
+    fn entity(&self) -> Entity;
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>);
+}
+
+impl WindowInputEvent for KeyboardInput {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.keyboard_input(ctx, self);
+    }
+}
+
+impl WindowInputEvent for ModifiersChanged {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.modifiers_changed(ctx, self.modifiers);
+    }
+}
+
+impl WindowInputEvent for CursorMoved {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.cursor_moved(ctx, self);
+    }
+}
+
+impl WindowInputEvent for CursorEntered {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.cursor_entered(ctx, self);
+    }
+}
+
+impl WindowInputEvent for CursorLeft {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.cursor_left(ctx, self);
+    }
+}
+
+impl WindowInputEvent for MouseInput {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.mouse_input(ctx, self);
+    }
+}
+
+impl WindowInputEvent for MouseWheel {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.mouse_wheel(ctx, self);
+    }
+}
+
+impl WindowInputEvent for TouchInput {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.touch_input(ctx, self);
+    }
+}
+
+impl WindowInputEvent for ReceivedCharacter {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.received_character(ctx, self.character);
+    }
+}
+
+impl WindowInputEvent for ImeCommit {
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+    fn forward(&self, callbacks: &mut WindowCallbacks, ctx: WindowContext<'_>) {
+        callbacks.ime_commit(ctx, &self.text);
+    }
+}
+
+pub(crate) type WindowInputSystemState<'w, 's, E> = SystemState<EventReader<'w, 's, E>>;
+
+/// Registers `E` and the system forwarding it to the window callbacks. Also used for the
+/// window state events in [`events`](crate::events).
+pub(crate) fn add_window_input_event<E: WindowInputEvent>(
+    world: &mut World,
+    schedule: &mut Schedule,
+) {
+    world.init_resource::<Events<E>>();
+    {
+        let state = WindowInputSystemState::<E>::new(world);
+        world.insert_resource(state);
+    }
+
+    schedule.stage(CoreStages::EventUpdate, |stage: &mut SystemStage| {
+        stage.add_system(Events::<E>::update_system);
+        stage
+    });
+    schedule.stage(CoreStages::PreUpdate, |stage: &mut SystemStage| {
+        stage.add_system_set(
+            SystemSet::new()
+                .with_system(forward_input_to_windows_system::<E>.exclusive_system())
+                .after(WINDOW_LIFECYCLE_SYSTEMS),
+        );
+        stage
+    });
+}
+
+pub(crate) fn forward_input_to_windows_system<E: WindowInputEvent>(world: &mut World) {
+    // Guard to prevent unessersary work from being done.
+    if world.get_resource::<Events<E>>().unwrap().is_empty() {
+        return;
+    }
+
+    tracing::debug_span!("forward_input_to_windows_system");
+    world.resource_scope(|world, mut state: Mut<WindowInputSystemState<E>>| {
+        let events = state
+            .get_mut(world)
+            .iter()
+            .cloned()
+            .collect::<SmallVec<[_; 4]>>();
+        world.resource_scope(|world, mut window_callbacks: Mut<WindowCallbacksManager>| {
+            for event in events {
+                let entity = event.entity();
+                // The window may have been destroyed since the event was sent.
+                if let Some(callbacks) = window_callbacks.get_mut(entity) {
+                    event.forward(callbacks, WindowContext { entity, world });
+                }
+            }
+        });
+    });
+}
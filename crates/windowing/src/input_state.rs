@@ -0,0 +1,227 @@
+//! Keyboard and mouse state for systems that would rather poll than read input events.
+//!
+//! Both resources are brought up to date in `CoreStages::EventUpdate`, so "just pressed"
+//! and "just released" cover the input received since the previous frame.
+
+use bevy_ecs::{
+    prelude::{Entity, EventReader, ResMut},
+    system::assert_is_system,
+};
+use renderer::painter::Point;
+use smallvec::SmallVec;
+use utilities::tracing;
+
+use crate::{
+    events::Destroy,
+    input::{
+        CursorLeft, CursorMoved, KeyCode, KeyboardInput, ModifiersChanged, ModifiersState,
+        MouseButton, MouseInput, MouseWheel, ScrollDelta,
+    },
+};
+
+/// The keys held on whichever window has focus, by their physical position.
+#[derive(Debug, Default)]
+pub struct KeyboardState {
+    pressed: SmallVec<[KeyCode; 8]>,
+    just_pressed: SmallVec<[KeyCode; 4]>,
+    just_released: SmallVec<[KeyCode; 4]>,
+    modifiers: ModifiersState,
+    focused: Option<Entity>,
+}
+
+impl KeyboardState {
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    /// The key went down this frame, repeats while it is held do not count.
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed.contains(&key)
+    }
+
+    pub fn just_released(&self, key: KeyCode) -> bool {
+        self.just_released.contains(&key)
+    }
+
+    /// Every key held, in the order they were pressed.
+    pub fn pressed_keys(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.pressed.iter().copied()
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// The window that received the most recent key.
+    pub fn focused_window(&self) -> Option<Entity> {
+        self.focused
+    }
+
+    fn press(&mut self, key: KeyCode, repeat: bool, is_synthetic: bool) {
+        if !self.pressed.contains(&key) {
+            self.pressed.push(key);
+        }
+        // Keys already held when a window gains focus were not pressed just now.
+        if !repeat && !is_synthetic && !self.just_pressed.contains(&key) {
+            self.just_pressed.push(key);
+        }
+    }
+
+    fn release(&mut self, key: KeyCode) {
+        if let Some(index) = self.pressed.iter().position(|pressed| *pressed == key) {
+            self.pressed.remove(index);
+            if !self.just_released.contains(&key) {
+                self.just_released.push(key);
+            }
+        }
+    }
+}
+
+/// The mouse buttons held, where the cursor is over each window and how far the wheel
+/// scrolled this frame.
+#[derive(Debug, Default)]
+pub struct MouseState {
+    pressed: SmallVec<[MouseButton; 4]>,
+    just_pressed: SmallVec<[MouseButton; 4]>,
+    just_released: SmallVec<[MouseButton; 4]>,
+    cursors: SmallVec<[(Entity, Point); 4]>,
+    hovered: Option<Entity>,
+    scroll_lines: Point,
+    scroll_pixels: Point,
+}
+
+impl MouseState {
+    pub fn pressed(&self, button: MouseButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed.contains(&button)
+    }
+
+    pub fn just_released(&self, button: MouseButton) -> bool {
+        self.just_released.contains(&button)
+    }
+
+    /// Where the cursor is over `window` in logical units, `None` when it is outside.
+    pub fn cursor_position(&self, window: Entity) -> Option<Point> {
+        self.cursors
+            .iter()
+            .find_map(|(entity, position)| (*entity == window).then(|| *position))
+    }
+
+    /// The window the cursor last moved over, `None` once it leaves every window.
+    pub fn hovered_window(&self) -> Option<Entity> {
+        self.hovered
+    }
+
+    /// Lines scrolled this frame by mouse wheels.
+    pub fn scroll_lines(&self) -> Point {
+        self.scroll_lines
+    }
+
+    /// Logical units scrolled this frame by touchpads.
+    pub fn scroll_pixels(&self) -> Point {
+        self.scroll_pixels
+    }
+
+    fn set_cursor(&mut self, window: Entity, position: Option<Point>) {
+        let index = self
+            .cursors
+            .iter()
+            .position(|(entity, _)| *entity == window);
+        match (index, position) {
+            (Some(index), Some(position)) => self.cursors[index].1 = position,
+            (None, Some(position)) => self.cursors.push((window, position)),
+            (Some(index), None) => {
+                self.cursors.remove(index);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+pub(crate) fn update_keyboard_state_system(
+    mut state: ResMut<KeyboardState>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut modifiers_events: EventReader<ModifiersChanged>,
+) {
+    assert_is_system(update_keyboard_state_system);
+    tracing::debug_span!("update_keyboard_state_system");
+    state.just_pressed.clear();
+    state.just_released.clear();
+
+    for event in modifiers_events.iter() {
+        state.modifiers = event.modifiers;
+    }
+    for event in keyboard_events.iter() {
+        state.focused = Some(event.entity);
+        if event.is_pressed() {
+            state.press(event.physical_key, event.repeat, event.is_synthetic);
+        } else {
+            state.release(event.physical_key);
+        }
+    }
+}
+
+pub(crate) fn update_mouse_state_system(
+    mut state: ResMut<MouseState>,
+    mut moved_events: EventReader<CursorMoved>,
+    mut left_events: EventReader<CursorLeft>,
+    mut button_events: EventReader<MouseInput>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut destroy_events: EventReader<Destroy>,
+) {
+    assert_is_system(update_mouse_state_system);
+    tracing::debug_span!("update_mouse_state_system");
+    state.just_pressed.clear();
+    state.just_released.clear();
+    state.scroll_lines = Point::default();
+    state.scroll_pixels = Point::default();
+
+    for event in moved_events.iter() {
+        state.set_cursor(event.entity, Some(event.position));
+        state.hovered = Some(event.entity);
+    }
+    for event in left_events.iter() {
+        state.set_cursor(event.entity, None);
+        if state.hovered == Some(event.entity) {
+            state.hovered = None;
+        }
+    }
+    for Destroy(entity) in destroy_events.iter() {
+        state.set_cursor(*entity, None);
+        if state.hovered == Some(*entity) {
+            state.hovered = None;
+        }
+    }
+
+    for event in button_events.iter() {
+        if event.is_pressed() {
+            if !state.pressed.contains(&event.button) {
+                state.pressed.push(event.button);
+            }
+            if !state.just_pressed.contains(&event.button) {
+                state.just_pressed.push(event.button);
+            }
+        } else if let Some(index) = state.pressed.iter().position(|b| *b == event.button) {
+            state.pressed.remove(index);
+            if !state.just_released.contains(&event.button) {
+                state.just_released.push(event.button);
+            }
+        }
+    }
+
+    for event in wheel_events.iter() {
+        match event.delta {
+            ScrollDelta::Lines { x, y } => {
+                state.scroll_lines.x += x;
+                state.scroll_lines.y += y;
+            }
+            ScrollDelta::Pixels { x, y } => {
+                state.scroll_pixels.x += x;
+                state.scroll_pixels.y += y;
+            }
+        }
+    }
+}
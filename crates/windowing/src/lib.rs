@@ -1,24 +1,39 @@
+pub mod actions;
 pub mod events;
+pub mod input;
+pub mod input_state;
 pub mod window;
 
 use bevy_ecs::{
     event::Events,
-    prelude::{Entity, EventReader, IntoExclusiveSystem, ResMut},
+    prelude::{Entity, EventReader, IntoExclusiveSystem, ParallelSystemDescriptorCoercion, ResMut},
     schedule::{Schedule, SystemSet, SystemStage},
     world::World, system::assert_is_system,
 };
 use events::{
-    create_surface_for_window_system, repaint_and_present_windows, CloseRequested,
-    CloseRequestedSystemState, Create, CreateWindowSystemState, Destroy, DestroyWindowSystemState,
-    Resize,
+    capture_frames_system, create_surface_for_window_system, repaint_and_present_windows,
+    CaptureFrame, CloseRequested, CloseRequestedSystemState, Create, CreateWindowSystemState,
+    Destroy, DestroyWindowSystemState, Focused, FrameCaptured, Minimized, Moved,
+    PaintWindowsSystemState, Resize, ScaleFactorChanged, ThemeChanged,
 };
+use input::{
+    CursorEntered, CursorLeft, CursorMoved, CursorPosition, ImeCommit, ImeEnabled, KeyboardInput,
+    LastCursorPosition, Modifiers, ModifiersChanged, ModifiersState, MouseButton, MouseInput,
+    MouseWheel, PrimaryTouch, ReceivedCharacter, ScrollDelta, TouchInput, TouchPhase,
+};
+use input_state::{KeyboardState, MouseState};
+use renderer::{painter::Point, WindowDrawTarget};
 pub use tao::event_loop::ControlFlow;
 use tao::{
-    event::{Event as TaoEvent, StartCause, WindowEvent as TaoWindowEvent},
+    dpi::PhysicalPosition,
+    event::{
+        ElementState, Event as TaoEvent, Force, MouseScrollDelta, StartCause,
+        WindowEvent as TaoWindowEvent,
+    },
     event_loop::EventLoop as TaoEventLoop,
 };
 use utilities::{tracing, CoreStages, EcsContext, EcsPlugin, Event};
-use window::{TaoWindowIdWapper, WindowCallbacksManager, WindowId};
+use window::{OsWindow, TaoWindowIdWapper, WindowCallbacksManager, WindowId, WindowState};
 
 use crate::events::Repaint;
 
@@ -34,8 +49,13 @@ impl EcsPlugin for WindowingPlugin {
         world.init_resource::<Events<Destroy>>();
         world.init_resource::<Events<ShutdownEventLoop>>();
         world.init_resource::<Events<CloseRequested>>();
+        world.init_resource::<Events<CaptureFrame>>();
+        world.init_resource::<Events<FrameCaptured>>();
 
         world.init_resource::<WindowCallbacksManager>();
+        world.init_resource::<QuitPolicy>();
+        world.init_resource::<KeyboardState>();
+        world.init_resource::<MouseState>();
         {
             let state = CreateWindowSystemState::new(world);
             world.insert_resource(state);
@@ -48,6 +68,10 @@ impl EcsPlugin for WindowingPlugin {
             let state = CloseRequestedSystemState::new(world);
             world.insert_resource(state);
         }
+        {
+            let state = PaintWindowsSystemState::new(world);
+            world.insert_resource(state);
+        }
 
         // Systems ========================================================
 
@@ -58,6 +82,10 @@ impl EcsPlugin for WindowingPlugin {
             stage.add_system(Events::<Destroy>::update_system);
             stage.add_system(Events::<CloseRequested>::update_system);
             stage.add_system(Events::<ShutdownEventLoop>::update_system);
+            stage.add_system(Events::<CaptureFrame>::update_system);
+            stage.add_system(Events::<FrameCaptured>::update_system);
+            stage.add_system(input_state::update_keyboard_state_system);
+            stage.add_system(input_state::update_mouse_state_system);
             stage
         });
         schedule.stage(CoreStages::PreUpdate, |stage: &mut SystemStage| {
@@ -81,14 +109,47 @@ impl EcsPlugin for WindowingPlugin {
             stage.add_system(create_surface_for_window_system);
             stage
         });
+        schedule.stage(CoreStages::Layout, |stage: &mut SystemStage| {
+            stage.add_system(events::repaint_changed_elements_system);
+            stage
+        });
+        schedule.stage(CoreStages::Render, |stage: &mut SystemStage| {
+            // Exclusive systems run at the start of the stage, so the element trees are
+            // drawn on top of whatever the windows painted themselves.
+            stage.add_system(events::paint_windows_system.exclusive_system());
+            stage.add_system(events::render_element_trees_system);
+            stage
+        });
         schedule.stage(CoreStages::Present, |stage: &mut SystemStage| {
-            stage.add_system(repaint_and_present_windows);
+            stage.add_system(repaint_and_present_windows.label(events::PRESENT_WINDOWS_SYSTEMS));
+            stage.add_system(capture_frames_system.after(events::PRESENT_WINDOWS_SYSTEMS));
             stage
         });
         schedule.stage(CoreStages::Last, |stage: &mut SystemStage| {
             stage.add_system(shutdown_eventloop_system);
             stage
         });
+
+        // Input ========================================================
+
+        input::add_window_input_event::<KeyboardInput>(world, schedule);
+        input::add_window_input_event::<ModifiersChanged>(world, schedule);
+        input::add_window_input_event::<CursorMoved>(world, schedule);
+        input::add_window_input_event::<CursorEntered>(world, schedule);
+        input::add_window_input_event::<CursorLeft>(world, schedule);
+        input::add_window_input_event::<MouseInput>(world, schedule);
+        input::add_window_input_event::<MouseWheel>(world, schedule);
+        input::add_window_input_event::<TouchInput>(world, schedule);
+        input::add_window_input_event::<ReceivedCharacter>(world, schedule);
+        input::add_window_input_event::<ImeCommit>(world, schedule);
+
+        // Window state ========================================================
+
+        input::add_window_input_event::<Focused>(world, schedule);
+        input::add_window_input_event::<Moved>(world, schedule);
+        input::add_window_input_event::<Minimized>(world, schedule);
+        input::add_window_input_event::<ThemeChanged>(world, schedule);
+        input::add_window_input_event::<ScaleFactorChanged>(world, schedule);
     }
 }
 
@@ -129,14 +190,8 @@ impl EventLoop {
                     ..
                 } => Self::dispatch_window_events(window_event, window_id, &mut ctx),
                 TaoEvent::RedrawRequested(raw_id) => {
-                    let window_id = find_window_id_from_raw_id(raw_id, &mut ctx);
-                    if let Some(entity) = ctx
-                        .world
-                        .query::<(Entity, &WindowId)>()
-                        .iter(&mut ctx.world)
-                        .find_map(|(entity, &id)| if window_id == id { Some(entity) } else { None })
-                    {
-                        ctx.post_event(Repaint(entity));
+                    if let Some(entity) = find_window_entity_from_raw_id(raw_id, &mut ctx) {
+                        ctx.post_event(Repaint::all(entity));
                     }
                 }
                 TaoEvent::MainEventsCleared => {
@@ -157,32 +212,221 @@ impl EventLoop {
         ctx: &mut EcsContext<D>,
     ) {
         tracing::debug_span!("EventLoop::dispatch_window_events");
+        if blocked_by_modal(&window_event) {
+            if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                if let Some(modal) = window::blocking_modal(&mut ctx.world, entity) {
+                    // Clicking the parent brings its modal back to the front instead
+                    if let TaoWindowEvent::MouseInput {
+                        state: ElementState::Pressed,
+                        ..
+                    } = window_event
+                    {
+                        if let Some(os_window) = ctx.world.get::<OsWindow>(modal) {
+                            os_window.set_focus();
+                        }
+                    }
+                    return;
+                }
+            }
+        }
         match window_event {
             TaoWindowEvent::CloseRequested => {
                 tracing::debug_span!("EventLoop::dispatch_window_events", "CloseRequested");
-                let window_id = find_window_id_from_raw_id(raw_id, ctx);
-                if let Some(entity) = ctx
-                    .world
-                    .query::<(Entity, &WindowId)>()
-                    .iter(&mut ctx.world)
-                    .find_map(|(entity, &id)| if window_id == id { Some(entity) } else { None })
-                {
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
                     ctx.post_event(CloseRequested(entity));
                 }
             }
             TaoWindowEvent::Destroyed => {
                 // TODO: manually emit this event from the window event handler system
             }
-            TaoWindowEvent::Resized(_) => {
+            TaoWindowEvent::Resized(size) => {
                 tracing::debug_span!("EventLoop::dispatch_window_events", "Resized");
-                let window_id = find_window_id_from_raw_id(raw_id, ctx);
-                if let Some(entity) = ctx
-                    .world
-                    .query::<(Entity, &WindowId)>()
-                    .iter(&mut ctx.world)
-                    .find_map(|(entity, &id)| if window_id == id { Some(entity) } else { None })
-                {
+                if let Some((entity, window_id)) = find_window_from_raw_id(raw_id, ctx) {
                     ctx.post_event(Resize { window_id, entity });
+                    // Minimized windows are resized to nothing on Windows, other platforms
+                    // keep their size and give no way to tell.
+                    let minimized = cfg!(windows) && size.width == 0 && size.height == 0;
+                    let was_minimized = ctx
+                        .world
+                        .get::<WindowState>(entity)
+                        .map_or(minimized, |state| state.minimized);
+                    if was_minimized != minimized {
+                        if let Some(mut state) = ctx.world.get_mut::<WindowState>(entity) {
+                            state.minimized = minimized;
+                        }
+                        ctx.post_event(Minimized { entity, minimized });
+                    }
+                }
+            }
+            TaoWindowEvent::Moved(position) => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "Moved");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let position = logical_position(ctx, entity, position.cast());
+                    ctx.post_event(Moved { entity, position });
+                }
+            }
+            TaoWindowEvent::Focused(focused) => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "Focused");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    if let Some(mut state) = ctx.world.get_mut::<WindowState>(entity) {
+                        state.focused = focused;
+                    }
+                    ctx.post_event(Focused { entity, focused });
+                }
+            }
+            TaoWindowEvent::ThemeChanged(theme) => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "ThemeChanged");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    ctx.post_event(ThemeChanged { entity, theme });
+                }
+            }
+            TaoWindowEvent::ModifiersChanged(modifiers) => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "ModifiersChanged");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    if let Some(mut current) = ctx.world.get_mut::<Modifiers>(entity) {
+                        current.0 = modifiers;
+                    }
+                    ctx.post_event(ModifiersChanged { entity, modifiers });
+                }
+            }
+            TaoWindowEvent::KeyboardInput {
+                event,
+                is_synthetic,
+                ..
+            } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "KeyboardInput");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let modifiers = window_modifiers(ctx, entity);
+                    ctx.post_event(KeyboardInput {
+                        entity,
+                        physical_key: event.physical_key,
+                        logical_key: event.logical_key,
+                        text: event.text,
+                        location: event.location,
+                        state: event.state,
+                        repeat: event.repeat,
+                        modifiers,
+                        is_synthetic,
+                    });
+                    if let Some(text) = event.text {
+                        let is_shortcut = modifiers.control_key() || modifiers.super_key();
+                        if event.state == ElementState::Pressed && !is_synthetic && !is_shortcut {
+                            send_received_characters(ctx, entity, text);
+                        }
+                    }
+                }
+            }
+            TaoWindowEvent::ReceivedImeText(text) => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "ReceivedImeText");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let enabled = ctx
+                        .world
+                        .get::<ImeEnabled>(entity)
+                        .map_or(false, |enabled| enabled.0);
+                    if enabled {
+                        send_received_characters(ctx, entity, &text);
+                        ctx.post_event(ImeCommit { entity, text });
+                    }
+                }
+            }
+            TaoWindowEvent::CursorMoved { position, .. } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "CursorMoved");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let position = logical_position(ctx, entity, position);
+                    set_cursor_position(ctx, entity, position);
+                    ctx.post_event(CursorMoved { entity, position });
+                }
+            }
+            TaoWindowEvent::CursorEntered { .. } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "CursorEntered");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let position = cursor_position(ctx, entity);
+                    ctx.post_event(CursorEntered { entity, position });
+                }
+            }
+            TaoWindowEvent::CursorLeft { .. } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "CursorLeft");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let position = cursor_position(ctx, entity);
+                    if let Some(mut cursor) = ctx.world.get_mut::<CursorPosition>(entity) {
+                        cursor.0 = None;
+                    }
+                    ctx.post_event(CursorLeft { entity, position });
+                }
+            }
+            TaoWindowEvent::MouseInput { state, button, .. } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "MouseInput");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let position = cursor_position(ctx, entity);
+                    let modifiers = window_modifiers(ctx, entity);
+                    ctx.post_event(MouseInput {
+                        entity,
+                        button,
+                        state,
+                        position,
+                        modifiers,
+                    });
+                }
+            }
+            TaoWindowEvent::MouseWheel { delta, phase, .. } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "MouseWheel");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => ScrollDelta::Lines { x, y },
+                        MouseScrollDelta::PixelDelta(delta) => {
+                            let delta = logical_position(ctx, entity, delta);
+                            ScrollDelta::Pixels {
+                                x: delta.x,
+                                y: delta.y,
+                            }
+                        }
+                        _ => return,
+                    };
+                    let position = cursor_position(ctx, entity);
+                    let modifiers = window_modifiers(ctx, entity);
+                    ctx.post_event(MouseWheel {
+                        entity,
+                        delta,
+                        phase,
+                        position,
+                        modifiers,
+                    });
+                }
+            }
+            TaoWindowEvent::Touch(touch) => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "Touch");
+                if let Some(entity) = find_window_entity_from_raw_id(raw_id, ctx) {
+                    let position = logical_position(ctx, entity, touch.location);
+                    let altitude = match touch.force {
+                        Some(Force::Calibrated { altitude_angle, .. }) => altitude_angle,
+                        _ => None,
+                    };
+                    let is_primary = ctx
+                        .world
+                        .get_mut::<PrimaryTouch>(entity)
+                        .map_or(false, |mut primary| primary.track(touch.id, touch.phase));
+                    ctx.post_event(TouchInput {
+                        entity,
+                        id: touch.id,
+                        phase: touch.phase,
+                        position,
+                        pressure: touch.force.map(|force| force.normalized() as f32),
+                        altitude: altitude.map(|altitude| altitude as f32),
+                        is_primary,
+                    });
+                    if is_primary {
+                        emulate_mouse_from_touch(ctx, entity, touch.phase, position);
+                    }
+                }
+            }
+            TaoWindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                tracing::debug_span!("EventLoop::dispatch_window_events", "ScaleFactorChanged");
+                if let Some((entity, window_id)) = find_window_from_raw_id(raw_id, ctx) {
+                    ctx.post_event(ScaleFactorChanged {
+                        window_id,
+                        entity,
+                        scale_factor,
+                    });
                 }
             }
             _ => (),
@@ -190,27 +434,166 @@ impl EventLoop {
     }
 }
 
-fn find_window_id_from_raw_id<D>(
+// Neither lookup panics, a window can still send events, like losing focus or a last
+// redraw, after it or one of its parents has been destroyed.
+fn find_window_from_raw_id<D>(
     raw_id: tao::window::WindowId,
     ctx: &mut EcsContext<D>,
-) -> WindowId {
-    tracing::debug_span!("windowing::find_window_id_from_raw_id");
+) -> Option<(Entity, WindowId)> {
+    tracing::debug_span!("windowing::find_window_from_raw_id");
 
-    match ctx
-        .world
-        .query::<(&WindowId, &TaoWindowIdWapper)>()
+    ctx.world
+        .query::<(Entity, &WindowId, &TaoWindowIdWapper)>()
+        .iter(&ctx.world)
+        .find_map(|(entity, &id, &tid)| (TaoWindowIdWapper(raw_id) == tid).then(|| (entity, id)))
+}
+
+fn find_window_entity_from_raw_id<D>(
+    raw_id: tao::window::WindowId,
+    ctx: &mut EcsContext<D>,
+) -> Option<Entity> {
+    ctx.world
+        .query::<(Entity, &TaoWindowIdWapper)>()
         .iter(&ctx.world)
-        .find(|(_, &tid)| TaoWindowIdWapper(raw_id) == tid)
-    {
-        Some((&id, _)) => id,
-        None => panic!("no window with id: {:?} found", raw_id),
+        .find_map(|(entity, &tid)| (TaoWindowIdWapper(raw_id) == tid).then(|| entity))
+}
+
+/// Input and close requests a window ignores while it has a modal window open. Releases
+/// still get through, so keys, buttons and touches held when the modal opened do not stay
+/// pressed.
+fn blocked_by_modal(window_event: &TaoWindowEvent) -> bool {
+    match window_event {
+        TaoWindowEvent::KeyboardInput { event, .. } => event.state == ElementState::Pressed,
+        TaoWindowEvent::MouseInput { state, .. } => *state == ElementState::Pressed,
+        TaoWindowEvent::Touch(touch) => {
+            !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled)
+        }
+        TaoWindowEvent::CloseRequested
+        | TaoWindowEvent::ReceivedImeText(_)
+        | TaoWindowEvent::CursorMoved { .. }
+        | TaoWindowEvent::MouseWheel { .. } => true,
+        _ => false,
     }
 }
 
+/// Converts a position tao reports in physical pixels to the window's logical units.
+fn logical_position<D>(
+    ctx: &EcsContext<D>,
+    entity: Entity,
+    position: PhysicalPosition<f64>,
+) -> Point {
+    let scale_factor = ctx
+        .world
+        .get::<OsWindow>(entity)
+        .map_or(1.0, |os_window| WindowDrawTarget::scale_factor(os_window));
+    let position = position.to_logical::<f32>(scale_factor);
+    Point::new(position.x, position.y)
+}
+
+/// Sends the mouse events a left click and drag at `position` would. If no mouse is over
+/// the window, the emulated cursor enters it when the touch starts and leaves it when the
+/// touch ends.
+fn emulate_mouse_from_touch<D>(
+    ctx: &mut EcsContext<D>,
+    entity: Entity,
+    phase: TouchPhase,
+    position: Point,
+) {
+    if phase == TouchPhase::Started {
+        let entered_window = ctx
+            .world
+            .get::<CursorPosition>(entity)
+            .map_or(false, |cursor| cursor.0.is_none());
+        if let Some(mut primary) = ctx.world.get_mut::<PrimaryTouch>(entity) {
+            primary.entered_window = entered_window;
+        }
+    }
+    let entered_window = ctx
+        .world
+        .get::<PrimaryTouch>(entity)
+        .map_or(false, |primary| primary.entered_window);
+    set_cursor_position(ctx, entity, position);
+    let modifiers = window_modifiers(ctx, entity);
+    let state = match phase {
+        TouchPhase::Started => ElementState::Pressed,
+        TouchPhase::Ended | TouchPhase::Cancelled => ElementState::Released,
+        _ => {
+            ctx.post_event(CursorMoved { entity, position });
+            return;
+        }
+    };
+    if phase == TouchPhase::Started {
+        if entered_window {
+            ctx.post_event(CursorEntered { entity, position });
+        }
+        ctx.post_event(CursorMoved { entity, position });
+    }
+    ctx.post_event(MouseInput {
+        entity,
+        button: MouseButton::Left,
+        state,
+        position,
+        modifiers,
+    });
+    if state == ElementState::Released && entered_window {
+        if let Some(mut cursor) = ctx.world.get_mut::<CursorPosition>(entity) {
+            cursor.0 = None;
+        }
+        ctx.post_event(CursorLeft { entity, position });
+    }
+}
+
+fn send_received_characters<D>(ctx: &mut EcsContext<D>, entity: Entity, text: &str) {
+    for character in text.chars().filter(|character| !character.is_control()) {
+        ctx.post_event(ReceivedCharacter { entity, character });
+    }
+}
+
+/// Where the cursor is over the window, or was last seen over it while it is outside.
+fn cursor_position<D>(ctx: &EcsContext<D>, entity: Entity) -> Point {
+    ctx.world
+        .get::<LastCursorPosition>(entity)
+        .map_or_else(Default::default, |cursor| cursor.0)
+}
+
+fn set_cursor_position<D>(ctx: &mut EcsContext<D>, entity: Entity, position: Point) {
+    if let Some(mut cursor) = ctx.world.get_mut::<CursorPosition>(entity) {
+        cursor.0 = Some(position);
+    }
+    if let Some(mut cursor) = ctx.world.get_mut::<LastCursorPosition>(entity) {
+        cursor.0 = position;
+    }
+}
+
+fn window_modifiers<D>(ctx: &EcsContext<D>, entity: Entity) -> ModifiersState {
+    ctx.world
+        .get::<Modifiers>(entity)
+        .map_or_else(Default::default, |modifiers| modifiers.0)
+}
+
 #[derive(Default, Debug)]
 struct ShutdownEventLoop;
 impl Event for ShutdownEventLoop {}
 
+/// When closing windows quits the application, checked after windows are destroyed in
+/// `CoreStages::PostUpdate`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QuitPolicy {
+    /// Quit once every window has been closed and no new window is waiting to be created.
+    LastWindowClosed,
+    /// Quit when this window closes, even if others are still open.
+    MainWindowClosed(WindowId),
+    /// Only quit when a window calls
+    /// [`WindowContext::post_shutdown_message`](window::WindowContext::post_shutdown_message).
+    Manual,
+}
+
+impl Default for QuitPolicy {
+    fn default() -> Self {
+        QuitPolicy::LastWindowClosed
+    }
+}
+
 fn shutdown_eventloop_system(
     mut control_flow: ResMut<ControlFlow>,
     events: EventReader<ShutdownEventLoop>,
@@ -1,17 +1,35 @@
+use anyhow::Error;
 use bevy_ecs::{
     event::Events,
     prelude::{Bundle, Component, Entity, Mut, World},
 };
 use derive_deref::{Deref, DerefMut};
 use raw_window_handle::HasRawWindowHandle;
-use renderer::WindowDrawTarget;
+use renderer::{painter::Point, RenderElementTree, Surface, WindowDrawTarget};
+use smallvec::SmallVec;
 use std::sync::{atomic::AtomicU32};
+pub use tao::window::{CursorIcon, Theme, UserAttentionType};
 use tao::{
+    dpi::{LogicalPosition, LogicalSize},
     event_loop::EventLoopWindowTarget,
-    window::{Window as TaoWindow, WindowBuilder as TaoWindowBuilder, WindowId as TaoWindowId},
+    monitor::MonitorHandle,
+    window::{
+        Fullscreen, Icon, Window as TaoWindow, WindowBuilder as TaoWindowBuilder,
+        WindowId as TaoWindowId,
+    },
 };
+use utilities::tracing;
 
-use crate::ShutdownEventLoop;
+use crate::{
+    actions::InputScope,
+    events::Resize,
+    input::{
+        CursorEntered, CursorLeft, CursorMoved, CursorPosition, ImeEnabled, KeyboardInput,
+        LastCursorPosition, Modifiers, ModifiersState, MouseInput, MouseWheel, PrimaryTouch,
+        TouchInput,
+    },
+    ShutdownEventLoop,
+};
 
 static CURRENT_WINDOW_ID: AtomicU32 = AtomicU32::new(0);
 #[derive(Component, Clone, Copy, Hash, PartialEq, Eq, Debug)]
@@ -30,10 +48,33 @@ pub trait Window: std::fmt::Debug + Send + Sync + 'static {
     fn close_requested(&mut self, _: WindowContext<'_>) -> bool {
         true
     }
-    fn on_destroyed(&mut self, mut ctx: WindowContext<'_>) {
-        ctx.post_shutdown_message();
-    }
-    // fn paint(&mut self, painter: impl Painter) {}
+    /// Called before the window is despawned, after the windows it is the parent of. When
+    /// the application quits is up to the [`QuitPolicy`](crate::QuitPolicy).
+    fn on_destroyed(&mut self, _: WindowContext<'_>) {}
+    /// Called in `CoreStages::Render` whenever the window is repainted, before the
+    /// surface is presented.
+    fn paint(&mut self, _surface: &mut Surface) {}
+    /// Called in `CoreStages::PreUpdate` for every key pressed or released while the
+    /// window has focus.
+    fn keyboard_input(&mut self, _: WindowContext<'_>, _: &KeyboardInput) {}
+    fn modifiers_changed(&mut self, _: WindowContext<'_>, _: ModifiersState) {}
+    fn cursor_moved(&mut self, _: WindowContext<'_>, _: &CursorMoved) {}
+    fn cursor_entered(&mut self, _: WindowContext<'_>, _: &CursorEntered) {}
+    fn cursor_left(&mut self, _: WindowContext<'_>, _: &CursorLeft) {}
+    fn mouse_input(&mut self, _: WindowContext<'_>, _: &MouseInput) {}
+    fn mouse_wheel(&mut self, _: WindowContext<'_>, _: &MouseWheel) {}
+    fn touch_input(&mut self, _: WindowContext<'_>, _: &TouchInput) {}
+    fn received_character(&mut self, _: WindowContext<'_>, _: char) {}
+    fn ime_commit(&mut self, _: WindowContext<'_>, _: &str) {}
+    /// Called in `CoreStages::PreUpdate` when the window gains or loses keyboard focus.
+    fn focused(&mut self, _: WindowContext<'_>, _: bool) {}
+    /// Called with the new top left corner of the window on the desktop, in logical units.
+    fn moved(&mut self, _: WindowContext<'_>, _: Point) {}
+    /// Called when the window is minimized or brought back, only on Windows, see
+    /// [`Minimized`](crate::events::Minimized).
+    fn minimized(&mut self, _: WindowContext<'_>, _: bool) {}
+    fn theme_changed(&mut self, _: WindowContext<'_>, _: Theme) {}
+    fn scale_factor_changed(&mut self, _: WindowContext<'_>, _: f64) {}
 }
 
 #[derive(Default, Debug)]
@@ -93,23 +134,358 @@ impl WindowDrawTarget for OsWindow {
     fn get_draw_bounds(&self) -> (u32, u32) {
         self.inner_size().into()
     }
+    fn scale_factor(&self) -> f64 {
+        self.0.scale_factor()
+    }
+}
+
+/// Takes the place of the [`OsWindow`] of windows created while
+/// [`HeadlessRendering`](renderer::HeadlessRendering) is inserted. They only have a size,
+/// [`WindowContext`] methods that need an OS window do nothing for them.
+#[derive(Component, Debug, Copy, Clone)]
+pub struct HeadlessWindow {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) scale_factor: f64,
+}
+
+impl HeadlessWindow {
+    /// The size tao gives windows that do not ask for one, in logical units.
+    const DEFAULT_INNER_SIZE: (f32, f32) = (800.0, 600.0);
+
+    pub(crate) fn new(descriptor: &WindowDescriptor, scale_factor: f64) -> Self {
+        let mut window = Self {
+            width: 0,
+            height: 0,
+            scale_factor,
+        };
+        let (width, height) = descriptor.inner_size.unwrap_or(Self::DEFAULT_INNER_SIZE);
+        window.set_inner_size(width, height);
+        window
+    }
+
+    /// The size of the drawable area in physical pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// The size of the drawable area in logical units.
+    pub fn inner_size(&self) -> (f32, f32) {
+        let scale_factor = self.scale_factor as f32;
+        (
+            self.width as f32 / scale_factor,
+            self.height as f32 / scale_factor,
+        )
+    }
+
+    fn set_inner_size(&mut self, width: f32, height: f32) {
+        let scale_factor = self.scale_factor as f32;
+        self.width = (width * scale_factor).round() as u32;
+        self.height = (height * scale_factor).round() as u32;
+    }
 }
 
 #[derive(Component, Deref, DerefMut)]
 #[repr(transparent)]
 pub struct RootEntitiy(Entity);
 
+/// What tao cannot be asked about a window, kept up to date from its events.
+#[derive(Component, Debug, Copy, Clone, Default)]
+pub(crate) struct WindowState {
+    pub(crate) focused: bool,
+    pub(crate) minimized: bool,
+}
+
 #[derive(Component)]
 #[repr(transparent)]
 pub struct Marker;
 
+/// Added to windows that were opened with a parent, see [`WindowDescriptor::with_parent`].
+/// Query for it to walk the window hierarchy.
+#[derive(Component, Debug, Copy, Clone)]
+pub struct WindowParent {
+    pub(crate) parent: Entity,
+    pub(crate) parent_id: WindowId,
+    pub(crate) modal: bool,
+}
+
+impl WindowParent {
+    pub fn parent(&self) -> Entity {
+        self.parent
+    }
+
+    /// The id the parent was created with.
+    pub fn parent_id(&self) -> WindowId {
+        self.parent_id
+    }
+
+    /// The parent ignores input while this window is open.
+    pub fn is_modal(&self) -> bool {
+        self.modal
+    }
+}
+
+pub(crate) fn find_window_entity(world: &mut World, window_id: WindowId) -> Option<Entity> {
+    world
+        .query::<(Entity, &WindowId)>()
+        .iter(world)
+        .find_map(|(entity, &id)| if window_id == id { Some(entity) } else { None })
+}
+
+pub(crate) fn window_children(world: &mut World, entity: Entity) -> SmallVec<[Entity; 4]> {
+    world
+        .query::<(Entity, &WindowParent)>()
+        .iter(world)
+        .filter(|(_, parent)| parent.parent == entity)
+        .map(|(child, _)| child)
+        .collect()
+}
+
+/// The modal window keeping `entity` from receiving input, if it has one open.
+pub(crate) fn blocking_modal(world: &mut World, entity: Entity) -> Option<Entity> {
+    world
+        .query::<(Entity, &WindowParent)>()
+        .iter(world)
+        .find_map(|(child, parent)| (parent.modal && parent.parent == entity).then(|| child))
+}
+
+/// Pushes the windows below `entity` in the hierarchy followed by `entity` itself, so
+/// children always come before their parent.
+pub(crate) fn collect_window_tree(
+    world: &mut World,
+    entity: Entity,
+    windows: &mut SmallVec<[Entity; 4]>,
+) {
+    if windows.contains(&entity) {
+        return;
+    }
+    for child in window_children(world, entity) {
+        collect_window_tree(world, child, windows);
+    }
+    windows.push(entity);
+}
+
+/// How a window starts out, passed along with its callbacks when creating it. Sizes and
+/// positions are in logical units.
+#[derive(Debug, Clone)]
+pub struct WindowDescriptor {
+    pub title: String,
+    /// The size of the drawable area, `None` lets the OS pick.
+    pub inner_size: Option<(f32, f32)>,
+    pub min_inner_size: Option<(f32, f32)>,
+    pub max_inner_size: Option<(f32, f32)>,
+    /// The top left corner of the window, decorations included, `None` lets the OS pick.
+    pub position: Option<(f32, f32)>,
+    pub resizable: bool,
+    pub decorations: bool,
+    /// Lets the desktop show through wherever the surface is not opaque.
+    pub transparent: bool,
+    pub always_on_top: bool,
+    pub visible: bool,
+    pub icon: Option<WindowIcon>,
+    pub fullscreen: FullscreenMode,
+    /// The window this one belongs to, it is closed along with its parent.
+    pub parent: Option<WindowId>,
+    /// Blocks input to the parent while this window is open.
+    pub modal: bool,
+}
+
+impl WindowDescriptor {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_inner_size(mut self, width: f32, height: f32) -> Self {
+        self.inner_size = Some((width, height));
+        self
+    }
+
+    pub fn with_min_inner_size(mut self, width: f32, height: f32) -> Self {
+        self.min_inner_size = Some((width, height));
+        self
+    }
+
+    pub fn with_max_inner_size(mut self, width: f32, height: f32) -> Self {
+        self.max_inner_size = Some((width, height));
+        self
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn with_decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn with_always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn with_icon(mut self, icon: WindowIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn with_fullscreen(mut self, fullscreen: FullscreenMode) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// Makes this a child of `parent`. tao cannot make one window own another on every
+    /// platform, so the OS still treats children as separate top level windows.
+    pub fn with_parent(mut self, parent: WindowId) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Makes this a modal dialog of `parent`, which ignores input until it is closed.
+    pub fn with_modal(mut self, parent: WindowId) -> Self {
+        self.parent = Some(parent);
+        self.modal = true;
+        self
+    }
+
+    fn builder(&self, event_loop: &EventLoopWindowTarget<()>) -> TaoWindowBuilder {
+        let fullscreen = match self.fullscreen.to_tao(event_loop.primary_monitor()) {
+            Ok(fullscreen) => fullscreen,
+            Err(err) => {
+                tracing::warn!("{}, opening {:?} windowed instead", err, self.title);
+                None
+            }
+        };
+        let mut builder = TaoWindowBuilder::new()
+            .with_title(self.title.as_str())
+            .with_resizable(self.resizable)
+            .with_decorations(self.decorations)
+            .with_transparent(self.transparent)
+            .with_always_on_top(self.always_on_top)
+            .with_visible(self.visible)
+            .with_fullscreen(fullscreen);
+        if let Some((width, height)) = self.inner_size {
+            builder = builder.with_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((width, height)) = self.min_inner_size {
+            builder = builder.with_min_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((width, height)) = self.max_inner_size {
+            builder = builder.with_max_inner_size(LogicalSize::new(width, height));
+        }
+        if let Some((x, y)) = self.position {
+            builder = builder.with_position(LogicalPosition::new(x, y));
+        }
+        if let Some(icon) = &self.icon {
+            builder = builder.with_window_icon(Some(icon.0.clone()));
+        }
+        builder
+    }
+}
+
+impl Default for WindowDescriptor {
+    fn default() -> Self {
+        Self {
+            title: "Untitled Window".to_string(),
+            inner_size: None,
+            min_inner_size: None,
+            max_inner_size: None,
+            position: None,
+            resizable: true,
+            decorations: true,
+            transparent: false,
+            always_on_top: false,
+            visible: true,
+            icon: None,
+            fullscreen: FullscreenMode::Windowed,
+            parent: None,
+            modal: false,
+        }
+    }
+}
+
+/// An image shown in the title bar and task bar.
+#[derive(Debug, Clone)]
+pub struct WindowIcon(Icon);
+
+impl WindowIcon {
+    /// Creates an icon from unpremultiplied RGBA8888 pixels, fails unless `rgba` holds
+    /// exactly `width` by `height` pixels.
+    pub fn new(rgba: Vec<u8>, width: u32, height: u32) -> anyhow::Result<Self> {
+        let expected_len = width as usize * height as usize * 4;
+        if rgba.len() != expected_len {
+            return Err(Error::msg(format!(
+                "A {}x{} icon needs {} bytes of RGBA, got {}",
+                width,
+                height,
+                expected_len,
+                rgba.len()
+            )));
+        }
+        Ok(Self(Icon::from_rgba(rgba, width, height)?))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// A borderless window covering the monitor the window is on.
+    Borderless,
+    /// Takes over a monitor at its largest video mode, the primary monitor for new windows
+    /// and the one the window is on otherwise. New windows open windowed when there is no
+    /// primary monitor or it has no video modes.
+    Exclusive,
+}
+
+impl FullscreenMode {
+    fn to_tao(self, monitor: Option<MonitorHandle>) -> anyhow::Result<Option<Fullscreen>> {
+        match self {
+            FullscreenMode::Windowed => Ok(None),
+            FullscreenMode::Borderless => Ok(Some(Fullscreen::Borderless(None))),
+            FullscreenMode::Exclusive => monitor
+                .and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|mode| {
+                        let size = mode.size();
+                        (size.width * size.height, mode.refresh_rate())
+                    })
+                })
+                .map_or_else(
+                    || Err(Error::msg("No video mode for exclusive fullscreen")),
+                    |mode| Ok(Some(Fullscreen::Exclusive(mode))),
+                ),
+        }
+    }
+}
+
 #[derive(Bundle)]
 pub struct WindowBundle {
-    _m: Marker,
-    pub(crate) id: WindowId,
+    #[bundle]
+    pub(crate) components: WindowComponents,
     pub(crate) os_window: OsWindow,
     pub(crate) raw_id: TaoWindowIdWapper,
-    pub(crate) root: RootEntitiy,
 }
 
 impl WindowBundle {
@@ -117,23 +493,74 @@ impl WindowBundle {
         id: WindowId,
         event_loop: &EventLoopWindowTarget<()>,
         root: Entity,
+        descriptor: &WindowDescriptor,
     ) -> anyhow::Result<Self> {
-        let os_window = OsWindow(
-            TaoWindowBuilder::new()
-                .with_title("Untitled Window")
-                .build(event_loop)?,
-        );
+        let os_window = OsWindow(descriptor.builder(event_loop).build(event_loop)?);
         let raw_id = TaoWindowIdWapper(os_window.id());
         Ok(Self {
-            _m: Marker,
-            id,
+            components: WindowComponents::new(id, root),
             os_window,
             raw_id,
-            root: RootEntitiy(root),
         })
     }
 }
 
+/// A window without an OS window, see [`HeadlessWindow`].
+#[derive(Bundle)]
+pub struct HeadlessWindowBundle {
+    #[bundle]
+    pub(crate) components: WindowComponents,
+    pub(crate) headless_window: HeadlessWindow,
+}
+
+impl HeadlessWindowBundle {
+    pub fn new(
+        id: WindowId,
+        root: Entity,
+        descriptor: &WindowDescriptor,
+        scale_factor: f64,
+    ) -> Self {
+        Self {
+            components: WindowComponents::new(id, root),
+            headless_window: HeadlessWindow::new(descriptor, scale_factor),
+        }
+    }
+}
+
+/// What every window has, whether it has an OS window or not.
+#[derive(Bundle)]
+pub(crate) struct WindowComponents {
+    _m: Marker,
+    pub(crate) id: WindowId,
+    pub(crate) root: RootEntitiy,
+    pub(crate) render_tree: RenderElementTree,
+    pub(crate) modifiers: Modifiers,
+    pub(crate) cursor_position: CursorPosition,
+    pub(crate) last_cursor_position: LastCursorPosition,
+    pub(crate) primary_touch: PrimaryTouch,
+    pub(crate) ime_enabled: ImeEnabled,
+    pub(crate) input_scope: InputScope,
+    pub(crate) state: WindowState,
+}
+
+impl WindowComponents {
+    fn new(id: WindowId, root: Entity) -> Self {
+        Self {
+            _m: Marker,
+            id,
+            root: RootEntitiy(root),
+            render_tree: RenderElementTree::new(),
+            modifiers: Modifiers::default(),
+            cursor_position: CursorPosition::default(),
+            last_cursor_position: LastCursorPosition::default(),
+            primary_touch: PrimaryTouch::default(),
+            ime_enabled: ImeEnabled::default(),
+            input_scope: InputScope::default(),
+            state: WindowState::default(),
+        }
+    }
+}
+
 pub struct WindowContext<'w> {
     // pub(crate) os_window: &'a mut OsWindow,
     pub(crate) entity: Entity,
@@ -142,8 +569,250 @@ pub struct WindowContext<'w> {
 
 impl WindowContext<'_> {
     pub fn set_title_text(&mut self, title: impl AsRef<str>) {
-        self.get_underlying_os_window_mut()
-            .set_title(title.as_ref());
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_title(title.as_ref());
+        }
+    }
+
+    /// Resizes the drawable area of the window, in logical units.
+    pub fn set_inner_size(&mut self, width: f32, height: f32) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_inner_size(LogicalSize::new(width, height));
+            return;
+        }
+        // OS windows send a `Resize` once they have been resized, headless windows are
+        // resized right away.
+        let entity = self.entity;
+        self.world
+            .get_mut::<HeadlessWindow>(entity)
+            .expect("no HeadlessWindow on this Enity")
+            .set_inner_size(width, height);
+        let window_id = *self
+            .world
+            .get::<WindowId>(entity)
+            .expect("no WindowId on this Enity");
+        self.world
+            .get_resource_mut::<Events<Resize>>()
+            .unwrap_or_else(|| panic!("No Events<Resize> Resource"))
+            .send(Resize { window_id, entity });
+    }
+
+    /// The size of the drawable area of the window, in logical units.
+    pub fn inner_size(&self) -> (f32, f32) {
+        match self.get_underlying_os_window() {
+            Some(os_window) => os_window
+                .inner_size()
+                .to_logical::<f32>(os_window.scale_factor())
+                .into(),
+            None => self.get_headless_window().inner_size(),
+        }
+    }
+
+    /// The size of the window including its decorations, in logical units.
+    pub fn outer_size(&self) -> (f32, f32) {
+        match self.get_underlying_os_window() {
+            Some(os_window) => os_window
+                .outer_size()
+                .to_logical::<f32>(os_window.scale_factor())
+                .into(),
+            None => self.get_headless_window().inner_size(),
+        }
+    }
+
+    /// Moves the top left corner of the window, decorations included, to `x`, `y` on the
+    /// desktop in logical units. Does nothing on Wayland.
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_outer_position(LogicalPosition::new(x, y));
+        }
+    }
+
+    /// Where the top left corner of the window is on the desktop in logical units, `None`
+    /// on Wayland where windows cannot know and for headless windows.
+    pub fn position(&self) -> Option<(f32, f32)> {
+        let os_window = self.get_underlying_os_window()?;
+        os_window
+            .outer_position()
+            .ok()
+            .map(|position| position.to_logical::<f32>(os_window.scale_factor()).into())
+    }
+
+    pub fn minimize(&mut self) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_minimized(true);
+        }
+    }
+
+    pub fn maximize(&mut self) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_maximized(true);
+        }
+    }
+
+    /// Brings a minimized window back the way it was, still maximized if it was before,
+    /// otherwise un-maximizes the window. Relies on [`WindowContext::is_minimized`], so
+    /// outside Windows a minimized window is un-maximized as well.
+    pub fn restore(&mut self) {
+        let minimized = self.is_minimized();
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_minimized(false);
+            if !minimized {
+                os_window.set_maximized(false);
+            }
+        }
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.get_underlying_os_window()
+            .map_or(false, |os_window| os_window.is_maximized())
+    }
+
+    /// Only tracked on Windows, always `false` on other platforms, see
+    /// [`Minimized`](crate::events::Minimized).
+    pub fn is_minimized(&self) -> bool {
+        self.get_window_state().minimized
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.get_window_state().focused
+    }
+
+    /// Brings the window to the front and gives it keyboard focus.
+    pub fn focus(&mut self) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_focus();
+        }
+    }
+
+    pub fn show(&mut self) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_visible(true);
+        }
+    }
+
+    pub fn hide(&mut self) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_visible(false);
+        }
+    }
+
+    /// Always `true` for headless windows.
+    pub fn is_visible(&self) -> bool {
+        self.get_underlying_os_window()
+            .map_or(true, |os_window| os_window.is_visible())
+    }
+
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) -> anyhow::Result<()> {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_fullscreen(mode.to_tao(os_window.current_monitor())?);
+        }
+        Ok(())
+    }
+
+    /// Switches between borderless fullscreen and windowed.
+    pub fn toggle_fullscreen(&mut self) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            match os_window.fullscreen() {
+                Some(_) => os_window.set_fullscreen(None),
+                None => os_window.set_fullscreen(Some(Fullscreen::Borderless(None))),
+            }
+        }
+    }
+
+    pub fn fullscreen(&self) -> FullscreenMode {
+        match self
+            .get_underlying_os_window()
+            .and_then(|os_window| os_window.fullscreen())
+        {
+            None => FullscreenMode::Windowed,
+            Some(Fullscreen::Exclusive(_)) => FullscreenMode::Exclusive,
+            Some(_) => FullscreenMode::Borderless,
+        }
+    }
+
+    /// The cursor shown while it is over the window.
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_cursor_icon(icon);
+        }
+    }
+
+    /// Hides the cursor while it is over the window.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_cursor_visible(visible);
+        }
+    }
+
+    /// Keeps the cursor inside the window, fails where the platform does not allow it.
+    pub fn set_cursor_grab(&mut self, grab: bool) -> anyhow::Result<()> {
+        match self.get_underlying_os_window() {
+            Some(os_window) => Ok(os_window.set_cursor_grab(grab)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Flashes the window in the task bar or bounces its dock icon until it is focused,
+    /// `None` stops a request that is still going.
+    pub fn request_user_attention(&mut self, request: Option<UserAttentionType>) {
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.request_user_attention(request);
+        }
+    }
+
+    /// Lets the input method compose text for this window, it is enabled by default.
+    /// tao cannot turn the OS input method off, a disabled window just ignores its text.
+    pub fn set_ime_enabled(&mut self, enabled: bool) {
+        self.world
+            .get_mut::<ImeEnabled>(self.entity)
+            .expect("no ImeEnabled on this Enity")
+            .0 = enabled;
+    }
+
+    /// Moves the input method's candidate window to `position`, in logical units from the
+    /// top left of the window. Usually just below the text cursor.
+    pub fn set_ime_position(&mut self, position: impl Into<Point>) {
+        let position = position.into();
+        if let Some(os_window) = self.get_underlying_os_window() {
+            os_window.set_ime_position(LogicalPosition::new(position.x, position.y));
+        }
+    }
+
+    /// Makes the window trigger the action bindings of `scope` along with the global
+    /// ones, see [`ActionBindings`](crate::actions::ActionBindings).
+    pub fn set_input_scope(&mut self, scope: Option<impl Into<String>>) {
+        self.world
+            .get_mut::<InputScope>(self.entity)
+            .expect("no InputScope on this Enity")
+            .0 = scope.map(Into::into);
+    }
+
+    /// The retained elements drawn into this window every time it is repainted.
+    pub fn render_tree_mut(&mut self) -> Mut<RenderElementTree> {
+        self.world
+            .get_mut::<RenderElementTree>(self.entity)
+            .expect("no RenderElementTree on this Enity")
+    }
+
+    /// The window this one was opened from.
+    pub fn parent(&self) -> Option<WindowId> {
+        self.world
+            .get::<WindowParent>(self.entity)
+            .map(WindowParent::parent_id)
+    }
+
+    /// The windows opened with this one as their parent.
+    pub fn children(&mut self) -> SmallVec<[WindowId; 4]> {
+        window_children(self.world, self.entity)
+            .into_iter()
+            .filter_map(|child| self.world.get::<WindowId>(child).copied())
+            .collect()
+    }
+
+    pub fn is_modal(&self) -> bool {
+        self.world
+            .get::<WindowParent>(self.entity)
+            .map_or(false, WindowParent::is_modal)
     }
 
     pub fn post_shutdown_message(&mut self) {
@@ -155,16 +824,19 @@ impl WindowContext<'_> {
 
     // ===========================================================================
 
-    #[allow(unused)]
-    fn get_underlying_os_window(&self) -> &OsWindow {
+    /// `None` for headless windows.
+    fn get_underlying_os_window(&self) -> Option<&OsWindow> {
+        self.world.get::<OsWindow>(self.entity)
+    }
+    fn get_headless_window(&self) -> &HeadlessWindow {
         self.world
-            .get::<OsWindow>(self.entity)
-            .expect("no OsWindow on this Enity")
+            .get::<HeadlessWindow>(self.entity)
+            .expect("no OsWindow or HeadlessWindow on this Enity")
     }
-    fn get_underlying_os_window_mut(&mut self) -> Mut<OsWindow> {
+    fn get_window_state(&self) -> &WindowState {
         self.world
-            .get_mut::<OsWindow>(self.entity)
-            .expect("no OsWindow on this Enity")
+            .get::<WindowState>(self.entity)
+            .expect("no WindowState on this Enity")
     }
 }
 
@@ -0,0 +1,100 @@
+//! Runs the windowing schedule with [`HeadlessRendering`], which opens no OS windows and
+//! so needs no display, and checks the pixels that were presented.
+
+use bevy_ecs::prelude::Entity;
+use renderer::{
+    painter::{Brush, Painter},
+    HeadlessRendering, Surface,
+};
+use utilities::EcsContext;
+use windowing::{
+    events::{Create, Repaint},
+    window::{HeadlessWindow, Window, WindowDescriptor, WindowId},
+    ControlFlow, WindowingPlugin,
+};
+
+#[derive(Debug)]
+struct SquareWindow;
+
+impl Window for SquareWindow {
+    fn paint(&mut self, surface: &mut Surface) {
+        surface.clear((255, 255, 255));
+        surface.draw_rect(
+            (8.0, 8.0, 16.0, 16.0),
+            &Brush::fill((200, 40, 40)).with_anti_alias(false),
+        );
+    }
+}
+
+fn headless_app() -> EcsContext<()> {
+    let mut ctx = EcsContext::new(());
+    ctx.add_plugin::<WindowingPlugin>();
+    ctx.world.insert_resource(HeadlessRendering {
+        frame_capacity: 2,
+        scale_factor: 2.0,
+    });
+    // Inserted by the event loop when it starts.
+    ctx.world.insert_resource(ControlFlow::Wait);
+    ctx
+}
+
+#[test]
+fn headless_windows_are_painted_and_presented_without_a_display() {
+    let mut ctx = headless_app();
+    let window_id = WindowId::new();
+    ctx.post_event(Create {
+        window: Box::new(SquareWindow),
+        window_id,
+        descriptor: WindowDescriptor::new("Headless").with_inner_size(32.0, 24.0),
+    });
+
+    ctx.schedule.run_once(&mut ctx.world);
+
+    let windows = ctx
+        .world
+        .query::<(&WindowId, &HeadlessWindow, &Surface)>()
+        .iter(&ctx.world)
+        .map(|(&id, &headless_window, surface)| (id, headless_window, surface.frames().cloned()))
+        .collect::<Vec<_>>();
+    assert_eq!(windows.len(), 1);
+    let (id, headless_window, frames) = windows.into_iter().next().unwrap();
+    assert_eq!(id, window_id);
+    // Sizes are in logical units, the surface is twice as large at a scale factor of 2.
+    assert_eq!(headless_window.size(), (64, 48));
+    let frames = frames.expect("Headless windows get headless surfaces");
+    assert_eq!(frames.presented_count(), 1);
+
+    let frame = frames.latest().unwrap();
+    assert_eq!((frame.width, frame.height), (64, 48));
+    assert_eq!(frame.pixel(0, 0), Some([255, 255, 255, 255]));
+    assert_eq!(frame.pixel(16, 16), Some([200, 40, 40, 255]));
+    assert_eq!(frame.pixel(47, 47), Some([200, 40, 40, 255]));
+    assert_eq!(frame.pixel(48, 40), Some([255, 255, 255, 255]));
+}
+
+#[test]
+fn headless_windows_are_only_presented_when_repainted() {
+    let mut ctx = headless_app();
+    ctx.post_event(Create {
+        window: Box::new(SquareWindow),
+        window_id: WindowId::new(),
+        descriptor: WindowDescriptor::new("Headless"),
+    });
+    ctx.schedule.run_once(&mut ctx.world);
+    ctx.schedule.run_once(&mut ctx.world);
+
+    let (entity, frames) = ctx
+        .world
+        .query::<(Entity, &Surface)>()
+        .iter(&ctx.world)
+        .map(|(entity, surface)| (entity, surface.frames().cloned().unwrap()))
+        .next()
+        .unwrap();
+    assert_eq!(frames.presented_count(), 1);
+    // Windows that ask for no size get the size tao would give them.
+    assert_eq!(frames.latest().unwrap().width, 1600);
+
+    ctx.post_event(Repaint::all(entity));
+    ctx.schedule.run_once(&mut ctx.world);
+    assert_eq!(frames.presented_count(), 2);
+}
@@ -1,21 +1,31 @@
 use application::{Application, ApplicationContext, run_app};
+use renderer::{
+    colors::Colour,
+    painter::{Brush, Stroke},
+    Painter, Surface,
+};
 use windowing::{
-    window::{WindowContext, WindowId, Window},
+    window::{Window, WindowContext, WindowDescriptor, WindowId},
     WindowingPlugin,
 };
 
 #[derive(Default, Debug)]
 pub struct MainWindow;
 impl Window for MainWindow {
-    fn on_create(&self, mut ctx: WindowContext<'_>) {
-        ctx.set_title_text("My Super Cool Window");
+    fn paint(&mut self, surface: &mut Surface) {
+        surface.clear(Colour::WHITE);
+        let card = (20.0, 20.0, 200.0, 120.0);
+        surface.draw_rounded_rect(card, 8.0, 8.0, &Brush::fill(Colour::ALICE_BLUE));
+        surface.draw_rounded_rect(
+            card,
+            8.0,
+            8.0,
+            &Brush::stroke(Colour::SLATE_GREY, Stroke::new(2.0)),
+        );
     }
     fn close_requested(&mut self, _: WindowContext<'_>) -> bool {
         true
     }
-    fn on_destroyed(&mut self, mut ctx: WindowContext<'_>) {
-        ctx.post_shutdown_message();
-    }
 }
 
 #[derive(Default)]
@@ -29,7 +39,10 @@ impl Application for BasicApplication {
         ctx.add_plugin::<WindowingPlugin>();
         // ctx.add_plugin::<RenderingPlugin>();
 
-        ctx.window = Some(ctx.create_window(MainWindow::default()));
+        ctx.window = Some(ctx.create_window(
+            MainWindow::default(),
+            WindowDescriptor::new("My Super Cool Window").with_inner_size(800.0, 600.0),
+        ));
     }
 }
 